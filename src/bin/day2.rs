@@ -1,13 +1,14 @@
+use std::fmt;
 use std::io;
 use std::io::Read;
 use ascent::ascent;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum RPS {
     Rock, Paper, Scissors
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Result {
     P1Win, P2Win, Draw
 }
@@ -81,46 +82,389 @@ pub fn follow_guide2(list: Vec<(RPS, Result)>) -> i32 {
         .sum()
 }
 
-pub fn parse1(input: &str) -> Vec<(RPS, RPS)> {
-    let mut list: Vec<(RPS, RPS)> = Vec::new();
-    for line in input.lines() {
-        let (p1_s, p2_s) = line.split_once(" ").unwrap();
-        let p1 = match p1_s {
-            "A" => RPS::Rock,
-            "B" => RPS::Paper,
-            "C" => RPS::Scissors,
-            _ => panic!("Unexpected string {}", p1_s)
-        };
-        let p2 = match p2_s {
-            "X" => RPS::Rock,
-            "Y" => RPS::Paper,
-            "Z" => RPS::Scissors,
-            _ => panic!("Unexpected string {}", p2_s)
+pub struct Analysis {
+    pub guide: i32,
+    pub best: i32,
+    pub worst: i32,
+}
+
+impl Analysis {
+    pub fn gap(&self) -> i32 {
+        return self.best - self.guide;
+    }
+}
+
+#[derive(Default)]
+pub struct Constraints {
+    pub max_losses: Option<usize>,
+    pub max_per_shape: Option<usize>,
+}
+
+const SHAPES: [RPS; 3] = [Rock, Paper, Scissors];
+
+fn index(shape: &RPS) -> usize {
+    return match shape {
+        Rock => 0,
+        Paper => 1,
+        Scissors => 2,
+    };
+}
+
+// `None` when no strategy meets the constraints
+pub fn analyse(list: Vec<(RPS, RPS)>, constraints: &Constraints) -> Option<Analysis> {
+    let opponent: Vec<RPS> = list.iter().map(|(p1, _)| *p1).collect();
+    return Some(Analysis {
+        best: best_score(&opponent, constraints)?,
+        worst: worst_score(&opponent, constraints)?,
+        guide: follow_guide1(list),
+    });
+}
+
+struct Edge {
+    to: usize,
+    cap: i32,
+    cost: i32,
+}
+
+struct Network {
+    edges: Vec<Edge>,
+    adj: Vec<Vec<usize>>,
+}
+
+impl Network {
+    fn with_nodes(n: usize) -> Self {
+        return Network { edges: vec![], adj: vec![vec![]; n] };
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, cap: i32, cost: i32) {
+        self.adj[from].push(self.edges.len());
+        self.edges.push(Edge { to, cap, cost });
+        self.adj[to].push(self.edges.len());
+        self.edges.push(Edge { to: from, cap: 0, cost: -cost });
+    }
+
+    // successive shortest paths, returns None when `needed` units can't be pushed
+    fn min_cost_flow(&mut self, source: usize, sink: usize, needed: i32) -> Option<i32> {
+        let mut flow = 0;
+        let mut cost = 0;
+        while flow < needed {
+            let mut dist = vec![i32::MAX; self.adj.len()];
+            let mut via: Vec<Option<usize>> = vec![None; self.adj.len()];
+            dist[source] = 0;
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for node in 0..self.adj.len() {
+                    if dist[node] == i32::MAX {
+                        continue;
+                    }
+                    for &e in &self.adj[node] {
+                        let edge = &self.edges[e];
+                        if edge.cap > 0 && dist[node] + edge.cost < dist[edge.to] {
+                            dist[edge.to] = dist[node] + edge.cost;
+                            via[edge.to] = Some(e);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if dist[sink] == i32::MAX {
+                return None;
+            }
+
+            let mut push = needed - flow;
+            let mut node = sink;
+            while let Some(e) = via[node] {
+                push = push.min(self.edges[e].cap);
+                node = self.edges[e ^ 1].to;
+            }
+            let mut node = sink;
+            while let Some(e) = via[node] {
+                self.edges[e].cap -= push;
+                self.edges[e ^ 1].cap += push;
+                node = self.edges[e ^ 1].to;
+            }
+            flow += push;
+            cost += push * dist[sink];
+        }
+        return Some(cost);
+    }
+}
+
+enum LossLimit {
+    Unlimited,
+    // one budget for all rounds, only sound when maximising the score
+    Shared(i32),
+    PerOpponentShape([i32; 3]),
+}
+
+// `sign` is 1 to maximise the score and -1 to minimise it
+fn extreme_score(counts: [i32; 3], payoffs: &[[(i32, bool); 3]; 3], max_per_shape: Option<usize>, losses: &LossLimit, sign: i32) -> Option<i32> {
+    // nodes: source, opponent shapes, loss budget in/out, own shapes, sink
+    const SOURCE: usize = 0;
+    const LOSS_IN: usize = 4;
+    const LOSS_OUT: usize = 5;
+    const SINK: usize = 9;
+    let opp_node = |s: &RPS| 1 + index(s);
+    let own_node = |s: &RPS| 6 + index(s);
+    let rounds: i32 = counts.iter().sum();
+    let cost = |p1: &RPS, p2: &RPS| -sign * payoffs[index(p1)][index(p2)].0;
+    let lost = |p1: &RPS, p2: &RPS| payoffs[index(p1)][index(p2)].1;
+
+    let mut net = Network::with_nodes(10);
+    for p1 in &SHAPES {
+        net.add_edge(SOURCE, opp_node(p1), counts[index(p1)], 0);
+        for p2 in &SHAPES {
+            match losses {
+                LossLimit::PerOpponentShape(caps) if lost(p1, p2) => net.add_edge(opp_node(p1), own_node(p2), caps[index(p1)], cost(p1, p2)),
+                LossLimit::Shared(_) if lost(p1, p2) => {},
+                _ => net.add_edge(opp_node(p1), own_node(p2), rounds, cost(p1, p2)),
+            }
+        }
+        if let LossLimit::Shared(_) = losses {
+            net.add_edge(opp_node(p1), LOSS_IN, rounds, 0);
+        }
+    }
+    if let LossLimit::Shared(max_losses) = losses {
+        // a non-losing pair routed through here scores less than its direct edge,
+        // so an optimal flow only spends this budget on actual losses
+        net.add_edge(LOSS_IN, LOSS_OUT, *max_losses, 0);
+        for p2 in &SHAPES {
+            let winner = SHAPES.iter().find(|p1| lost(p1, p2)).unwrap();
+            net.add_edge(LOSS_OUT, own_node(p2), rounds, cost(winner, p2));
+        }
+    }
+    let per_shape = max_per_shape.map_or(rounds, |m| m as i32);
+    for p2 in &SHAPES {
+        net.add_edge(own_node(p2), SINK, per_shape, 0);
+    }
+
+    return net.min_cost_flow(SOURCE, SINK, rounds).map(|c| -sign * c);
+}
+
+fn shape_counts(opponent: &[RPS]) -> [i32; 3] {
+    return SHAPES.map(|p1| opponent.iter().filter(|&&o| o == p1).count() as i32);
+}
+
+// score and whether it is a loss for every opponent shape and own shape
+fn payoffs() -> [[(i32, bool); 3]; 3] {
+    return SHAPES.map(|p1| SHAPES.map(|p2| (score(&p1, &p2), play(&p1, &p2) == P1Win)));
+}
+
+pub fn best_score(opponent: &[RPS], constraints: &Constraints) -> Option<i32> {
+    let losses = constraints.max_losses.map_or(LossLimit::Unlimited, |m| LossLimit::Shared(m as i32));
+    return extreme_score(shape_counts(opponent), &payoffs(), constraints.max_per_shape, &losses, 1);
+}
+
+// Minimising, a shared loss budget would be spent on winning rounds priced as losses, so the
+// losses allowed against rocks and papers are enumerated instead, with scissors taking the
+// rest of the budget: O(n²) flows when losses are limited.
+pub fn worst_score(opponent: &[RPS], constraints: &Constraints) -> Option<i32> {
+    let counts = shape_counts(opponent);
+    let payoffs = payoffs();
+    let Some(max_losses) = constraints.max_losses.map(|m| m as i32) else {
+        return extreme_score(counts, &payoffs, constraints.max_per_shape, &LossLimit::Unlimited, -1);
+    };
+    let mut worst = None;
+    for rocks in 0..=counts[0].min(max_losses) {
+        for papers in 0..=counts[1].min(max_losses - rocks) {
+            let losses = LossLimit::PerOpponentShape([rocks, papers, max_losses - rocks - papers]);
+            let score = extreme_score(counts, &payoffs, constraints.max_per_shape, &losses, -1);
+            worst = worst.into_iter().chain(score).min();
+        }
+    }
+    return worst;
+}
+
+pub struct Column<T> {
+    symbols: Vec<(String, T)>,
+    ignore_case: bool,
+}
+
+impl<T: Copy> Column<T> {
+    pub fn new(symbols: &[(&str, T)]) -> Self {
+        return Column {
+            symbols: symbols.iter().map(|(s, v)| (s.to_string(), *v)).collect(),
+            ignore_case: false,
         };
-        list.push((p1, p2));
     }
-    return list;
+
+    pub fn case_insensitive(mut self) -> Self {
+        self.ignore_case = true;
+        return self;
+    }
+
+    fn decode(&self, token: &str) -> Option<T> {
+        return self.symbols.iter()
+            .find(|(s, _)| if self.ignore_case { s.eq_ignore_ascii_case(token) } else { s == token })
+            .map(|(_, v)| *v);
+    }
+}
+
+pub fn opponent_letters() -> Column<RPS> {
+    return Column::new(&[("A", Rock), ("B", Paper), ("C", Scissors)]);
+}
+
+pub fn own_letters() -> Column<RPS> {
+    return Column::new(&[("X", Rock), ("Y", Paper), ("Z", Scissors)]);
+}
+
+pub fn result_letters() -> Column<Result> {
+    return Column::new(&[("X", P1Win), ("Y", Draw), ("Z", P2Win)]);
+}
+
+pub fn shape_words() -> Column<RPS> {
+    return Column::new(&[("rock", Rock), ("paper", Paper), ("scissors", Scissors)]).case_insensitive();
+}
+
+pub fn result_words() -> Column<Result> {
+    return Column::new(&[("lose", P1Win), ("draw", Draw), ("win", P2Win)]).case_insensitive();
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DecodeError {
+    pub line: usize,
+    pub token: String,
+}
+
+pub struct Decoder<T> {
+    opponent: Column<RPS>,
+    own: Column<T>,
+}
+
+impl<T: Copy> Decoder<T> {
+    pub fn new(opponent: Column<RPS>, own: Column<T>) -> Self {
+        return Decoder { opponent, own };
+    }
+
+    pub fn decode(&self, input: &str) -> std::result::Result<Vec<(RPS, T)>, DecodeError> {
+        let mut list = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = |token: &str| DecodeError { line: i + 1, token: token.to_owned() };
+            let (p1_s, p2_s) = line.trim().split_once(' ').ok_or_else(|| error(line))?;
+            let p1 = self.opponent.decode(p1_s.trim()).ok_or_else(|| error(p1_s))?;
+            let p2 = self.own.decode(p2_s.trim()).ok_or_else(|| error(p2_s))?;
+            list.push((p1, p2));
+        }
+        return Ok(list);
+    }
+}
+
+pub fn parse1(input: &str) -> Vec<(RPS, RPS)> {
+    return Decoder::new(opponent_letters(), own_letters()).decode(input).unwrap();
 }
 
 pub fn parse2(input: &str) -> Vec<(RPS, Result)> {
-    let mut list: Vec<(RPS, Result)> = Vec::new();
-    for line in input.lines() {
-        let (p1_s, p2_s) = line.split_once(" ").unwrap();
-        let p1 = match p1_s {
-            "A" => RPS::Rock,
-            "B" => RPS::Paper,
-            "C" => RPS::Scissors,
-            _ => panic!("Unexpected string {}", p1_s)
-        };
-        let p2 = match p2_s {
-            "X" => Result::P1Win,
-            "Y" => Result::Draw,
-            "Z" => Result::P2Win,
-            _ => panic!("Unexpected string {}", p2_s)
-        };
-        list.push((p1, p2));
+    return Decoder::new(opponent_letters(), result_letters()).decode(input).unwrap();
+}
+
+pub fn mappings_with_score(input: &str, target: i32) -> Vec<[RPS; 3]> {
+    let mut found = Vec::new();
+    for x in SHAPES {
+        for y in SHAPES.into_iter().filter(|&y| y != x) {
+            for z in SHAPES.into_iter().filter(|&z| z != x && z != y) {
+                let own = Column::new(&[("X", x), ("Y", y), ("Z", z)]);
+                if let Ok(list) = Decoder::new(opponent_letters(), own).decode(input) {
+                    if follow_guide1(list) == target {
+                        found.push([x, y, z]);
+                    }
+                }
+            }
+        }
+    }
+    return found;
+}
+
+pub fn shapes1(list: Vec<(RPS, RPS)>) -> Vec<RPS> {
+    return list.into_iter().map(|(_, p2)| p2).collect();
+}
+
+pub fn shapes2(list: Vec<(RPS, Result)>) -> Vec<RPS> {
+    return list.iter().map(|(p1, res)| pick_result(p1, res)).collect();
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub points: i32,
+}
+
+impl Standing {
+    // three for a won match and one for a drawn one
+    pub fn match_points(&self) -> usize {
+        return 3 * self.wins + self.draws;
+    }
+}
+
+pub struct Standings {
+    pub rows: Vec<Standing>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnequalGuides {
+    pub name: String,
+    pub rounds: usize,
+    pub expected: usize,
+}
+
+// every pair plays a match over all rounds of their guides, so the guides must be equally long;
+// players rank by match results and the summed round score only breaks ties
+pub fn tournament(players: &[(&str, Vec<RPS>)]) -> std::result::Result<Standings, UnequalGuides> {
+    if let Some((first, rest)) = players.split_first() {
+        if let Some((name, guide)) = rest.iter().find(|(_, guide)| guide.len() != first.1.len()) {
+            return Err(UnequalGuides { name: name.to_string(), rounds: guide.len(), expected: first.1.len() });
+        }
+    }
+
+    let mut rows: Vec<Standing> = players.iter()
+        .map(|(name, _)| Standing { name: name.to_string(), wins: 0, draws: 0, losses: 0, points: 0 })
+        .collect();
+
+    for i in 0..players.len() {
+        for j in (i + 1)..players.len() {
+            let mut rounds_i = 0;
+            let mut rounds_j = 0;
+            for (p_i, p_j) in players[i].1.iter().zip(&players[j].1) {
+                match play(p_i, p_j) {
+                    P1Win => rounds_i += 1,
+                    P2Win => rounds_j += 1,
+                    Draw => {},
+                }
+                rows[i].points += score(p_j, p_i);
+                rows[j].points += score(p_i, p_j);
+            }
+            if rounds_i > rounds_j {
+                rows[i].wins += 1;
+                rows[j].losses += 1;
+            } else if rounds_j > rounds_i {
+                rows[j].wins += 1;
+                rows[i].losses += 1;
+            } else {
+                rows[i].draws += 1;
+                rows[j].draws += 1;
+            }
+        }
+    }
+
+    rows.sort_by(|a, b| b.match_points().cmp(&a.match_points()).then(b.points.cmp(&a.points)));
+    return Ok(Standings { rows });
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.rows.iter().map(|r| r.name.len()).max().unwrap_or(0).max(6);
+        writeln!(f, "{:<width$} {:>4} {:>4} {:>4} {:>6}", "player", "W", "D", "L", "points")?;
+        for r in &self.rows {
+            writeln!(f, "{:<width$} {:>4} {:>4} {:>4} {:>6}", r.name, r.wins, r.draws, r.losses, r.points)?;
+        }
+        Ok(())
     }
-    return list;
 }
 
 fn main() {
@@ -157,4 +501,96 @@ mod tests {
         assert_eq!(follow_guide2(parse2(INPUT)), 12);
     }
 
+    #[test]
+    fn test_analyse() {
+        let analysis = analyse(parse1(INPUT), &Constraints::default()).unwrap();
+        assert_eq!(analysis.guide, 15);
+        assert_eq!(analysis.best, 24);
+        assert_eq!(analysis.worst, 6);
+        assert_eq!(analysis.gap(), 9);
+
+        let analysis = analyse(parse1(INPUT), &Constraints { max_losses: Some(1), max_per_shape: None }).unwrap();
+        assert_eq!((analysis.best, analysis.worst), (24, 11));
+        assert!(analyse(parse1(INPUT), &Constraints { max_losses: Some(0), max_per_shape: Some(0) }).is_none());
+    }
+
+    #[test]
+    fn test_best_score_constrained() {
+        let rocks = [Rock, Rock, Rock];
+        assert_eq!(best_score(&rocks, &Constraints::default()), Some(24));
+        let once_each = Constraints { max_losses: None, max_per_shape: Some(1) };
+        assert_eq!(best_score(&rocks, &once_each), Some(15));
+        let no_losses = Constraints { max_losses: Some(0), max_per_shape: Some(1) };
+        assert_eq!(best_score(&rocks, &no_losses), None);
+        let twice_no_losses = Constraints { max_losses: Some(0), max_per_shape: Some(2) };
+        assert_eq!(best_score(&rocks, &twice_no_losses), Some(20));
+        let one_loss = Constraints { max_losses: Some(1), max_per_shape: Some(1) };
+        assert_eq!(best_score(&rocks, &one_loss), Some(15));
+
+        assert_eq!(worst_score(&rocks, &Constraints::default()), Some(9));
+        assert_eq!(worst_score(&rocks, &once_each), Some(15));
+        assert_eq!(worst_score(&rocks, &no_losses), None);
+        assert_eq!(worst_score(&rocks, &twice_no_losses), Some(16));
+        assert_eq!(worst_score(&rocks, &one_loss), Some(15));
+        let mixed = [Rock, Paper, Scissors, Rock];
+        let one_loss_twice = Constraints { max_losses: Some(1), max_per_shape: Some(2) };
+        assert_eq!(worst_score(&mixed, &one_loss_twice), Some(15));
+    }
+
+    #[test]
+    fn test_decode_alternative_encodings() {
+        let words = indoc! {"
+            Rock paper
+            paper ROCK
+            scissors scissors
+        "};
+        let decoder = Decoder::new(shape_words(), shape_words());
+        assert_eq!(decoder.decode(words).map(follow_guide1), Ok(15));
+
+        let lowercase = Decoder::new(opponent_letters().case_insensitive(), result_letters().case_insensitive());
+        assert_eq!(lowercase.decode("a y\nb x\nc z\n").map(follow_guide2), Ok(12));
+
+        assert_eq!(lowercase.decode("a y\nb q\n"), Err(DecodeError { line: 2, token: "q".to_owned() }));
+    }
+
+    #[test]
+    fn test_mappings_with_score() {
+        assert_eq!(mappings_with_score(INPUT, 15).len(), 4);
+        assert_eq!(mappings_with_score(INPUT, 24), vec![[Scissors, Paper, Rock]]);
+        assert!(mappings_with_score(INPUT, 1000).is_empty());
+    }
+
+    #[test]
+    fn test_tournament() {
+        let standings = tournament(&[
+            ("guide1", shapes1(parse1(INPUT))),
+            ("guide2", shapes2(parse2(INPUT))),
+            ("papers", vec![Paper, Paper, Paper]),
+        ]).unwrap();
+        let table: Vec<_> = standings.rows.iter()
+            .map(|r| (r.name.as_str(), r.wins, r.draws, r.losses, r.points))
+            .collect();
+        assert_eq!(table, vec![
+            ("papers", 1, 1, 0, 39),
+            ("guide1", 0, 2, 0, 30),
+            ("guide2", 0, 1, 1, 15),
+        ]);
+        assert_eq!(standings.to_string().lines().nth(1), Some("papers    1    1    0     39"));
+
+        // match results outrank a higher round score
+        let standings = tournament(&[
+            ("rocks", vec![Rock, Rock]),
+            ("mixed", vec![Rock, Scissors]),
+            ("sharp", vec![Scissors, Paper]),
+        ]).unwrap();
+        let table: Vec<_> = standings.rows.iter().map(|r| (r.name.as_str(), r.match_points(), r.points)).collect();
+        assert_eq!(table, vec![("rocks", 4, 19), ("mixed", 3, 23), ("sharp", 1, 16)]);
+
+        assert!(tournament(&[]).unwrap().rows.is_empty());
+        assert_eq!(
+            tournament(&[("guide1", shapes1(parse1(INPUT))), ("short", vec![Rock])]).err(),
+            Some(UnequalGuides { name: "short".to_owned(), rounds: 1, expected: 3 }),
+        );
+    }
+
 }
\ No newline at end of file
@@ -1,15 +1,76 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
 use std::io::Read;
 
+use derive_more::{BitAnd, BitOr};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, BitAnd, BitOr, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Rucksack(u64);
+
+impl Rucksack {
+    pub fn from_items(items: &str) -> Self {
+        return Rucksack(items.chars().fold(0, |bits, c| bits | 1 << priority(c)));
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        return self & other;
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        return self | other;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.0.count_ones() as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.0 == 0;
+    }
+
+    pub fn priorities(&self) -> impl Iterator<Item = i32> {
+        let mut bits = self.0;
+        return std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let p = bits.trailing_zeros();
+            bits &= bits - 1;
+            return Some(p as i32);
+        });
+    }
+
+    pub fn priority_sum(&self) -> i32 {
+        return self.priorities().sum();
+    }
+}
+
+pub fn item(priority: i32) -> char {
+    let val = match priority {
+        1..=26 => b'a' + (priority - 1) as u8,
+        27..=52 => b'A' + (priority - 27) as u8,
+        p => panic!("Unexpected priority {}", p)
+    };
+    return val as char;
+}
+
 pub fn find_double(strs: &[&str]) -> char {
     let doubles = strs.iter()
-        .map(|s| s.chars().collect::<HashSet<char>>())
-        .reduce(|acc, item| acc.intersection(&item).copied().collect())
+        .map(|s| Rucksack::from_items(s))
+        .reduce(Rucksack::intersection)
         .unwrap();
 
     assert!(doubles.len() == 1);
-    return *doubles.iter().next().unwrap();
+    return item(doubles.priorities().next().unwrap());
+}
+
+pub fn shared_priority(strs: &[&str]) -> i32 {
+    return strs.iter()
+        .map(|s| Rucksack::from_items(s))
+        .reduce(Rucksack::intersection)
+        .unwrap_or_default()
+        .priority_sum();
 }
 
 pub fn priority(item: char) -> i32 {
@@ -30,14 +91,248 @@ pub fn shared_item(input: &str) -> i32 {
         .sum();
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Repacked {
+    pub arrangement: String,
+    pub moves: usize,
+}
+
+// a move swaps one item from each compartment, so both keep the same size
+pub fn repack(rucksack: &str) -> Option<Repacked> {
+    // compartments are counted in chars, not bytes
+    let mut items: Vec<char> = rucksack.chars().collect();
+    if items.len() % 2 == 1 {
+        return None;
+    }
+    let half = items.len() / 2;
+    let mut counts: BTreeMap<char, (usize, usize)> = BTreeMap::new();
+    for &c in &items[..half] {
+        counts.entry(c).or_insert((0, 0)).0 += 1;
+    }
+    for &c in &items[half..] {
+        counts.entry(c).or_insert((0, 0)).1 += 1;
+    }
+    let types: Vec<(char, usize, usize)> = counts.into_iter().map(|(c, (l, r))| (c, l, r)).collect();
+
+    // best[n] is the fewest misplaced items when exactly n items end up in the first compartment
+    let mut best = vec![usize::MAX; half + 1];
+    best[0] = 0;
+    let mut to_first = vec![vec![false; half + 1]; types.len()];
+    for (t, &(_, l, r)) in types.iter().enumerate() {
+        let mut next = vec![usize::MAX; half + 1];
+        for n in (0..=half).filter(|&n| best[n] != usize::MAX) {
+            if best[n] + l < next[n] {
+                next[n] = best[n] + l;
+                to_first[t][n] = false;
+            }
+            if n + l + r <= half && best[n] + r < next[n + l + r] {
+                next[n + l + r] = best[n] + r;
+                to_first[t][n + l + r] = true;
+            }
+        }
+        best = next;
+    }
+    if best[half] == usize::MAX {
+        return None;
+    }
+    let misplaced = best[half];
+
+    let mut in_first = HashSet::new();
+    let mut n = half;
+    for (t, &(c, l, r)) in types.iter().enumerate().rev() {
+        if to_first[t][n] {
+            in_first.insert(c);
+            n -= l + r;
+        }
+    }
+
+    let leaving_first: Vec<usize> = (0..half).filter(|&i| !in_first.contains(&items[i])).collect();
+    let leaving_second: Vec<usize> = (half..items.len()).filter(|&i| in_first.contains(&items[i])).collect();
+    for (&i, &j) in leaving_first.iter().zip(&leaving_second) {
+        items.swap(i, j);
+    }
+
+    return Some(Repacked {
+        arrangement: items.into_iter().collect(),
+        moves: misplaced / 2,
+    });
+}
+
+// groups are buffered in place and must share exactly one badge; `group_report` is the
+// slower path that explains malformed groups instead of failing on them
 pub fn shared_item2(input: &str) -> i32 {
+    let mut group = [""; 3];
+    let mut n = 0;
+    let mut sum = 0;
+    for line in input.lines() {
+        group[n] = line;
+        n += 1;
+        if n == group.len() {
+            sum += priority(find_double(&group));
+            n = 0;
+        }
+    }
+    assert!(n == 0, "Incomplete group of {} rucksacks", n);
+    return sum;
+}
+
+pub fn shared_items(strs: &[&str]) -> Vec<(char, usize)> {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for c in strs.iter().flat_map(|s| s.chars()) {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    return counts.into_iter()
+        .filter(|(c, _)| strs.iter().all(|s| s.contains(*c)))
+        .collect();
+}
+
+#[derive(Clone, Copy)]
+pub enum Grouping {
+    Chunks(usize),
+    Windows(usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Diagnostic {
+    NoSharedItem { group: usize },
+    SeveralSharedItems { group: usize, items: Vec<char> },
+    IncompleteGroup { group: usize, size: usize },
+    UnknownItem { group: usize, item: char },
+    EmptyGroups,
+}
+
+pub struct GroupReport {
+    pub groups: Vec<Vec<(char, usize)>>,
+    pub priority_sum: i32,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn group_report(input: &str, grouping: Grouping) -> GroupReport {
+    let mut report = GroupReport { groups: vec![], priority_sum: 0, diagnostics: vec![] };
+    if let Grouping::Chunks(0) | Grouping::Windows(0) = grouping {
+        report.diagnostics.push(Diagnostic::EmptyGroups);
+        return report;
+    }
     let all_lines: Vec<_> = input.lines().collect();
-    return all_lines.chunks(3)
-        .map(find_double)
-        .map(priority)
-        .sum();
+    let (mut groups, size): (Vec<&[&str]>, usize) = match grouping {
+        Grouping::Chunks(n) => (all_lines.chunks(n).collect(), n),
+        Grouping::Windows(n) => (all_lines.windows(n).collect(), n),
+    };
+    // too few lines for even one window still make one, incomplete group
+    if groups.is_empty() && !all_lines.is_empty() {
+        groups.push(&all_lines);
+    }
+
+    for (group, lines) in groups.into_iter().enumerate() {
+        if lines.len() < size {
+            report.diagnostics.push(Diagnostic::IncompleteGroup { group, size: lines.len() });
+        }
+        let shared = shared_items(lines);
+        match shared.len() {
+            0 => report.diagnostics.push(Diagnostic::NoSharedItem { group }),
+            1 => {},
+            _ => report.diagnostics.push(Diagnostic::SeveralSharedItems {
+                group,
+                items: shared.iter().map(|(c, _)| *c).collect(),
+            }),
+        }
+        for &(item, _) in &shared {
+            if item.is_ascii_alphabetic() {
+                report.priority_sum += priority(item);
+            } else {
+                report.diagnostics.push(Diagnostic::UnknownItem { group, item });
+            }
+        }
+        report.groups.push(shared);
+    }
+    return report;
+}
+
+
+pub trait PriorityScheme {
+    fn priority(&self, item: &str) -> Option<i32>;
+}
+
+pub struct AocPriority;
+
+impl PriorityScheme for AocPriority {
+    fn priority(&self, item: &str) -> Option<i32> {
+        let mut chars = item.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(priority(c)),
+            _ => None,
+        };
+    }
+}
+
+pub struct TablePriority {
+    table: HashMap<String, i32>,
+}
+
+impl TablePriority {
+    pub fn new(entries: &[(&str, i32)]) -> Self {
+        return TablePriority {
+            table: entries.iter().map(|(item, p)| (item.to_string(), *p)).collect(),
+        };
+    }
+
+    pub fn from_alphabet(alphabet: &str) -> Self {
+        return TablePriority {
+            table: items(alphabet).into_iter()
+                .zip(1..)
+                .map(|(item, p)| (item.to_owned(), p))
+                .collect(),
+        };
+    }
+}
+
+impl PriorityScheme for TablePriority {
+    fn priority(&self, item: &str) -> Option<i32> {
+        return self.table.get(item).copied();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownItem(pub String);
+
+pub fn items(s: &str) -> Vec<&str> {
+    return s.graphemes(true).collect();
+}
+
+pub fn shared_priority_with<P: PriorityScheme>(strs: &[Vec<&str>], scheme: &P) -> std::result::Result<i32, UnknownItem> {
+    let mut shared: Vec<&str> = strs[0].clone();
+    for other in &strs[1..] {
+        let other: HashSet<&str> = other.iter().copied().collect();
+        shared.retain(|item| other.contains(item));
+    }
+    shared.sort();
+    shared.dedup();
+
+    let mut sum = 0;
+    for item in shared {
+        sum += scheme.priority(item).ok_or_else(|| UnknownItem(item.to_owned()))?;
+    }
+    return Ok(sum);
 }
 
+pub fn shared_item_with<P: PriorityScheme>(input: &str, scheme: &P) -> std::result::Result<i32, UnknownItem> {
+    let mut sum = 0;
+    for line in input.lines() {
+        let mut first = items(line);
+        let second = first.split_off(first.len() / 2);
+        sum += shared_priority_with(&[first, second], scheme)?;
+    }
+    return Ok(sum);
+}
+
+pub fn shared_item2_with<P: PriorityScheme>(input: &str, scheme: &P) -> std::result::Result<i32, UnknownItem> {
+    let all_lines: Vec<Vec<&str>> = input.lines().map(items).collect();
+    let mut sum = 0;
+    for group in all_lines.chunks(3) {
+        sum += shared_priority_with(group, scheme)?;
+    }
+    return Ok(sum);
+}
 
 fn main() {
     let mut input = String::new();
@@ -71,8 +366,93 @@ mod tests {
         assert_eq!(shared_item(INPUT), 157);
     }
 
+    #[test]
+    #[should_panic]
+    fn test_shared_item2_incomplete() {
+        shared_item2(&INPUT.lines().take(4).collect::<Vec<_>>().join("\n"));
+    }
+
     #[test]
     fn test_shared_item2() {
         assert_eq!(shared_item2(INPUT), 70);
     }
+
+    #[test]
+    fn test_group_report() {
+        let report = group_report(INPUT, Grouping::Chunks(3));
+        assert_eq!(report.groups, vec![vec![('r', 6)], vec![('Z', 5)]]);
+        assert!(report.diagnostics.is_empty());
+
+        let report = group_report(INPUT, Grouping::Windows(2));
+        assert_eq!(report.groups.len(), 5);
+        assert_eq!(report.groups[0], vec![('F', 7), ('M', 3), ('f', 2), ('r', 5), ('s', 3)]);
+        assert_eq!(report.diagnostics[0], Diagnostic::SeveralSharedItems {
+            group: 0,
+            items: vec!['F', 'M', 'f', 'r', 's'],
+        });
+
+        let report = group_report(INPUT, Grouping::Chunks(4));
+        assert_eq!(report.diagnostics, vec![
+            Diagnostic::NoSharedItem { group: 0 },
+            Diagnostic::IncompleteGroup { group: 1, size: 2 },
+            Diagnostic::SeveralSharedItems { group: 1, items: vec!['G', 'J', 'Z'] },
+        ]);
+
+        let report = group_report("ab1\nb1c\n", Grouping::Chunks(2));
+        assert_eq!(report.priority_sum, 2);
+        assert_eq!(report.diagnostics, vec![
+            Diagnostic::SeveralSharedItems { group: 0, items: vec!['1', 'b'] },
+            Diagnostic::UnknownItem { group: 0, item: '1' },
+        ]);
+        let short = group_report("ab\nbc\n", Grouping::Windows(3));
+        assert_eq!(short.groups, vec![vec![('b', 2)]]);
+        assert_eq!(short.diagnostics, vec![Diagnostic::IncompleteGroup { group: 0, size: 2 }]);
+        assert_eq!(group_report("ab\nbc\n", Grouping::Chunks(3)).diagnostics, short.diagnostics);
+        assert_eq!(group_report(INPUT, Grouping::Chunks(0)).diagnostics, vec![Diagnostic::EmptyGroups]);
+        assert_eq!(group_report(INPUT, Grouping::Windows(0)).diagnostics, vec![Diagnostic::EmptyGroups]);
+    }
+
+    #[test]
+    fn test_rucksack() {
+        let a = Rucksack::from_items("abcZ");
+        let b = Rucksack::from_items("bZzz");
+        assert_eq!(a.intersection(b).priorities().map(item).collect::<String>(), "bZ");
+        assert_eq!(a.union(b).len(), 5);
+        assert_eq!(a.union(b).priority_sum(), 1 + 2 + 3 + 26 + 52);
+        assert!(Rucksack::default().is_empty());
+        assert_eq!(shared_priority(&INPUT.lines().take(3).collect::<Vec<_>>()), 18);
+    }
+
+    #[test]
+    fn test_priority_schemes() {
+        assert_eq!(shared_item_with(INPUT, &AocPriority), Ok(157));
+        assert_eq!(shared_item2_with(INPUT, &AocPriority), Ok(70));
+        assert_eq!(shared_item_with("a1b1", &AocPriority), Err(UnknownItem("1".to_owned())));
+
+        let table = TablePriority::new(&[("a", 10), ("b", 20)]);
+        assert_eq!(shared_item_with("abba", &table), Ok(30));
+
+        let greek = TablePriority::from_alphabet("αβγδ");
+        assert_eq!(shared_item_with("αβγβ\nδαγδ", &greek), Ok(2 + 4));
+
+        let accented = TablePriority::from_alphabet("e\u{301}e");
+        assert_eq!(items("e\u{301}ee\u{301}e"), vec!["e\u{301}", "e", "e\u{301}", "e"]);
+        assert_eq!(shared_item_with("e\u{301}ee\u{301}e", &accented), Ok(1 + 2));
+    }
+
+    #[test]
+    fn test_repack() {
+        let repacked = repack("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        let (a, b) = repacked.arrangement.split_at(12);
+        assert!(!a.chars().any(|c| b.contains(c)));
+        assert_eq!(repacked.moves, 1);
+
+        assert_eq!(repack("abcd"), Some(Repacked { arrangement: "abcd".to_owned(), moves: 0 }));
+        assert_eq!(repack("aabcbb"), Some(Repacked { arrangement: "aacbbb".to_owned(), moves: 1 }));
+        assert_eq!(repack("aaab"), None);
+        assert_eq!(repack("abc"), None);
+        assert_eq!(repack("éé"), None);
+        assert_eq!(repack("aébé"), Some(Repacked { arrangement: "ééba".to_owned(), moves: 1 }));
+        assert_eq!(repack("aébc"), Some(Repacked { arrangement: "aébc".to_owned(), moves: 0 }));
+    }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use std::io;
 use std::io::Read;
 
 type Range = (i32, i32);
+type Group = Vec<Range>;
 
 pub fn full_contains((s1,e1): Range, (s2,e2): Range) -> bool {
     return (s1 >= s2) && (e1 <= e2);
@@ -11,21 +12,212 @@ pub fn partial_overlap((s1,e1): Range, (s2,e2): Range) -> bool {
     return (s1 <= e2) && (e1 >= s2);
 }
 
-pub fn how_many_overlap<F: Fn(Range, Range) -> bool>(input: Vec<(Range,Range)>, contains: F) -> usize {
+#[derive(Clone, Copy)]
+pub enum Over {
+    Pairs,
+    Intersection,
+}
+
+fn intersection(ranges: impl Iterator<Item = Range>) -> Option<Range> {
+    return ranges
+        .map(Some)
+        .reduce(|a, b| match (a, b) {
+            (Some((s1, e1)), Some((s2, e2))) if s1.max(s2) <= e1.min(e2) => Some((s1.max(s2), e1.min(e2))),
+            _ => None,
+        })
+        .flatten();
+}
+
+pub fn group_overlaps<F: Fn(Range, Range) -> bool>(group: &[Range], contains: &F, over: Over) -> bool {
+    return match over {
+        Over::Pairs => (0..group.len())
+            .any(|i| ((i + 1)..group.len()).any(|j| contains(group[i], group[j]) || contains(group[j], group[i]))),
+        // compare each range against the common part of all the others
+        Over::Intersection => (0..group.len())
+            .any(|i| {
+                let others = group.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, r)| *r);
+                match intersection(others) {
+                    Some(rest) => contains(group[i], rest) || contains(rest, group[i]),
+                    None => false,
+                }
+            }),
+    };
+}
+
+pub fn how_many_overlap_over<F: Fn(Range, Range) -> bool>(input: Vec<Group>, contains: F, over: Over) -> usize {
     return input.iter()
-        .filter(|(r1, r2)| contains(*r1, *r2) || contains(*r2, *r1))
+        .filter(|group| group_overlaps(group, &contains, over))
         .count();
 }
 
-pub fn parse(input: &str) -> Vec<(Range,Range)> {
+pub fn how_many_overlap<F: Fn(Range, Range) -> bool>(input: Vec<Group>, contains: F) -> usize {
+    return how_many_overlap_over(input, contains, Over::Pairs);
+}
+
+pub fn pairs(groups: &[Group]) -> Option<Vec<(Range, Range)>> {
+    return groups.iter()
+        .map(|group| match group[..] {
+            [r1, r2] => Some((r1, r2)),
+            _ => None,
+        })
+        .collect();
+}
+
+pub fn assignments(groups: &[Group]) -> Vec<Range> {
+    return groups.iter().flatten().copied().collect();
+}
+
+pub struct Coverage {
+    // contiguous segments spanning all assignments, each with a constant depth
+    segments: Vec<(Range, usize)>,
+}
+
+impl Coverage {
+    pub fn sweep(assignments: &[Range]) -> Self {
+        let mut events: Vec<(i32, i32)> = assignments.iter()
+            .flat_map(|&(s, e)| [(s, 1), (e + 1, -1)])
+            .collect();
+        events.sort();
+
+        let mut segments = Vec::new();
+        let mut depth = 0;
+        let mut i = 0;
+        while i < events.len() {
+            let pos = events[i].0;
+            while i < events.len() && events[i].0 == pos {
+                depth += events[i].1;
+                i += 1;
+            }
+            if i < events.len() {
+                segments.push(((pos, events[i].0 - 1), depth as usize));
+            }
+        }
+        return Coverage { segments };
+    }
+
+    fn merged<F: Fn(usize) -> bool>(&self, keep: F) -> Vec<Range> {
+        let mut result: Vec<Range> = Vec::new();
+        for &((s, e), depth) in &self.segments {
+            if !keep(depth) {
+                continue;
+            }
+            match result.last_mut() {
+                Some(last) if last.1 + 1 == s => last.1 = e,
+                _ => result.push((s, e)),
+            }
+        }
+        return result;
+    }
+
+    pub fn uncovered(&self) -> Vec<Range> {
+        return self.merged(|depth| depth == 0);
+    }
+
+    pub fn covered_more_than(&self, k: usize) -> Vec<Range> {
+        return self.merged(|depth| depth > k);
+    }
+
+    pub fn max_depth(&self) -> usize {
+        return self.segments.iter().map(|(_, depth)| *depth).max().unwrap_or(0);
+    }
+
+    pub fn redundant(&self, assignments: &[Range]) -> Vec<usize> {
+        let single = self.merged(|depth| depth < 2);
+        return assignments.iter().enumerate()
+            .filter(|(_, &(s, e))| {
+                let i = single.partition_point(|&(_, end)| end < s);
+                i == single.len() || single[i].0 > e
+            })
+            .map(|(i, _)| i)
+            .collect();
+    }
+}
+
+fn minus((s1, e1): Range, (s2, e2): Range) -> Vec<Range> {
+    let mut pieces = Vec::new();
+    if s1 < s2 {
+        pieces.push((s1, e1.min(s2 - 1)));
+    }
+    if e1 > e2 {
+        pieces.push((s1.max(e2 + 1), e1));
+    }
+    return pieces;
+}
+
+fn len((s, e): Range) -> i32 {
+    return e - s + 1;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Reassignment {
+    pub pair: usize,
+    pub elf: usize,
+    pub from: Range,
+    pub to: Range,
+}
+
+fn shift_past(r: Range, (_, end): Range) -> Range {
+    return (end + 1, end + len(r));
+}
+
+fn shrink(r1: Range, r2: Range) -> (usize, Range) {
+    let longest = |pieces: Vec<Range>| pieces.into_iter().reduce(|a, b| if len(b) > len(a) { b } else { a });
+    if let Some(piece) = longest(minus(r2, r1)) {
+        return (1, piece);
+    }
+    if let Some(piece) = longest(minus(r1, r2)) {
+        return (0, piece);
+    }
+    return (1, shift_past(r2, r1));
+}
+
+fn shrink_keeping_coverage(r1: Range, r2: Range) -> (usize, Range) {
+    if let [piece] = minus(r2, r1)[..] {
+        return (1, piece);
+    }
+    if let [piece] = minus(r1, r2)[..] {
+        return (0, piece);
+    }
+    // one range contains the other, so the smaller one is free to move away
+    let union = (r1.0.min(r2.0), r1.1.max(r2.1));
+    if len(r1) >= len(r2) {
+        return (1, shift_past(r2, union));
+    }
+    return (0, shift_past(r1, union));
+}
+
+fn rebalance_with<F: Fn(Range, Range) -> (usize, Range)>(input: &[(Range, Range)], fix: F) -> Vec<Reassignment> {
+    return input.iter().enumerate()
+        .filter(|(_, (r1, r2))| partial_overlap(*r1, *r2))
+        .map(|(pair, &(r1, r2))| {
+            let (elf, to) = fix(r1, r2);
+            Reassignment { pair, elf, from: [r1, r2][elf], to }
+        })
+        .collect();
+}
+
+pub fn rebalance(input: &[(Range, Range)]) -> Vec<Reassignment> {
+    return rebalance_with(input, shrink);
+}
+
+pub fn rebalance_keeping_coverage(input: &[(Range, Range)]) -> Vec<Reassignment> {
+    return rebalance_with(input, shrink_keeping_coverage);
+}
+
+pub fn parse(input: &str) -> Vec<Group> {
     fn as_range(input: &str) -> Range {
-        let (s1, s2) = input.split_once("-").unwrap();
-        return (s1.parse().unwrap(), s2.parse().unwrap());
+        return match input.trim().split_once("-") {
+            Some((s1, s2)) => (s1.trim().parse().unwrap(), s2.trim().parse().unwrap()),
+            None => {
+                let section = input.trim().parse().unwrap();
+                (section, section)
+            }
+        };
     }
 
     return input.lines()
-        .map(|l: &str| l.split_once(",").unwrap())
-        .map(|(s1, s2): (&str, &str)| (as_range(s1), as_range(s2)))
+        .filter(|l: &&str| !l.trim().is_empty())
+        .map(|l: &str| l.split(",").map(as_range).collect())
         .collect();
 }
 
@@ -66,4 +258,50 @@ mod tests {
         assert_eq!(how_many_overlap(parse(INPUT), partial_overlap), 4);
     }
 
+    #[test]
+    fn test_coverage() {
+        let all = assignments(&parse(INPUT));
+        let coverage = Coverage::sweep(&all);
+        assert_eq!(coverage.uncovered(), vec![]);
+        assert_eq!(coverage.covered_more_than(6), vec![(4, 6)]);
+        assert_eq!(coverage.max_depth(), 8);
+        assert_eq!(coverage.redundant(&all), vec![0, 1, 2, 3, 4, 6, 7, 8, 9, 10, 11]);
+
+        let sparse = [(1, 3), (2, 3), (6, 9), (8, 8)];
+        let coverage = Coverage::sweep(&sparse);
+        assert_eq!(coverage.uncovered(), vec![(4, 5)]);
+        assert_eq!(coverage.covered_more_than(1), vec![(2, 3), (8, 8)]);
+        assert_eq!(coverage.max_depth(), 2);
+        assert_eq!(coverage.redundant(&sparse), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let pairs = pairs(&parse(INPUT)).unwrap();
+        let moved: Vec<_> = rebalance(&pairs).iter().map(|r| (r.pair, r.elf, r.to)).collect();
+        assert_eq!(moved, vec![(2, 1, (8, 9)), (3, 0, (2, 2)), (4, 1, (4, 5)), (5, 1, (7, 8))]);
+
+        let moved: Vec<_> = rebalance_keeping_coverage(&pairs).iter().map(|r| (r.pair, r.elf, r.to)).collect();
+        assert_eq!(moved, vec![(2, 1, (8, 9)), (3, 1, (9, 13)), (4, 1, (4, 5)), (5, 1, (7, 8))]);
+
+        assert_eq!(rebalance(&[((3, 3), (3, 3))]), vec![Reassignment { pair: 0, elf: 1, from: (3, 3), to: (4, 4) }]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let groups = parse(indoc! {"
+            2-4,3,4-9
+            1-2,3-4,5-6
+            7,7-7
+            1-9,2-8,3-7
+        "});
+        assert_eq!(groups[0], vec![(2, 4), (3, 3), (4, 9)]);
+        assert_eq!(pairs(&groups), None);
+        assert_eq!(how_many_overlap(groups.clone(), full_contains), 3);
+        assert_eq!(how_many_overlap(groups.clone(), partial_overlap), 3);
+        assert_eq!(how_many_overlap_over(groups.clone(), partial_overlap, Over::Intersection), 2);
+        assert_eq!(how_many_overlap_over(groups, full_contains, Over::Intersection), 2);
+        assert_eq!(how_many_overlap_over(parse(INPUT), full_contains, Over::Intersection), 2);
+    }
+
 }
\ No newline at end of file
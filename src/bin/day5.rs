@@ -1,4 +1,6 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::env;
 use std::io;
 use std::io::Read;
 use std::str::FromStr;
@@ -7,88 +9,420 @@ use regex::Regex;
 
 pub struct Crane {
     stacks: Vec<Stack>,
-    instructions: Vec<Instruction>
+    initial: Vec<Stack>,
+    instructions: Vec<Instruction>,
+    model: Box<dyn CraneModel>,
+    log: Vec<Move>,
 }
 
-type Stack = VecDeque<u8>;
+pub type Crate = String;
+pub type Stack = VecDeque<Crate>;
 
-struct Instruction {
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    line: usize,
     amount: usize,
     from: usize,
     to: usize,
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount, self.from, self.to)
+    }
+}
+
+pub enum Target {
+    Top(String),
+    Arrangement(Vec<Stack>),
+}
+
+struct PlanNode {
+    stacks: Vec<Stack>,
+    depth: usize,
+    parent: Option<(usize, Instruction)>,
+}
+
+impl Target {
+    fn reached(&self, stacks: &[Stack]) -> bool {
+        return match self {
+            Target::Top(top) => stacks.iter()
+                .map(|s| s.back().map_or(" ", |c| c.as_str()))
+                .collect::<String>() == *top,
+            Target::Arrangement(arrangement) => stacks == arrangement.as_slice(),
+        };
+    }
+}
+
+pub trait CraneModel {
+    fn name(&self) -> String;
+    // receives the crates taken off the source bottom to top, returns them in landing order
+    fn place(&self, instruction: usize, taken: &[Crate]) -> Vec<Crate>;
+    // how many instructions until `place` behaves the same again
+    fn period(&self) -> usize {
+        return 1;
+    }
+}
+
+pub struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn name(&self) -> String {
+        return "CrateMover 9000".to_owned();
+    }
+
+    fn place(&self, _: usize, taken: &[Crate]) -> Vec<Crate> {
+        return taken.iter().rev().cloned().collect();
+    }
+}
+
+pub struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn name(&self) -> String {
+        return "CrateMover 9001".to_owned();
+    }
+
+    fn place(&self, _: usize, taken: &[Crate]) -> Vec<Crate> {
+        return taken.to_vec();
+    }
+}
+
+pub struct Capped(pub usize);
+
+impl CraneModel for Capped {
+    fn name(&self) -> String {
+        return format!("capped at {}", self.0);
+    }
+
+    fn place(&self, _: usize, taken: &[Crate]) -> Vec<Crate> {
+        return taken.rchunks(self.0).flatten().cloned().collect();
+    }
+}
+
+pub struct Alternating;
+
+impl CraneModel for Alternating {
+    fn name(&self) -> String {
+        return "alternating".to_owned();
+    }
+
+    fn place(&self, instruction: usize, taken: &[Crate]) -> Vec<Crate> {
+        if instruction % 2 == 1 {
+            return CrateMover9001.place(instruction, taken);
+        }
+        return CrateMover9000.place(instruction, taken);
+    }
+
+    fn period(&self) -> usize {
+        return 2;
+    }
+}
+
+pub fn model(name: &str) -> Option<Box<dyn CraneModel>> {
+    return match name.split_once(':') {
+        None if name == "9000" => Some(Box::new(CrateMover9000)),
+        None if name == "9001" => Some(Box::new(CrateMover9001)),
+        None if name == "alternating" => Some(Box::new(Alternating)),
+        Some(("capped", cap)) => match cap.parse() {
+            Ok(cap) if cap > 0 => Some(Box::new(Capped(cap))),
+            _ => None,
+        },
+        _ => None,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub instruction: usize,
+    pub from: usize,
+    pub to: usize,
+    // bottom to top, as they stood on `from` and as they landed on `to`
+    pub taken: Vec<Crate>,
+    pub placed: Vec<Crate>,
+}
+
+fn apply(model: &dyn CraneModel, index: usize, stacks: &mut [Stack], instr: &Instruction) -> Move {
+    let from = &mut stacks[instr.from - 1];
+    let taken: Vec<Crate> = from.split_off(from.len() - instr.amount).into();
+    let placed = model.place(index, &taken);
+    stacks[instr.to - 1].extend(placed.iter().cloned());
+    return Move {
+        instruction: index,
+        from: instr.from,
+        to: instr.to,
+        taken,
+        placed,
+    };
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    NoSuchStack(usize),
+    NotEnoughCrates { requested: usize, available: usize },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidInstruction {
+    pub line: usize,
+    pub problem: Problem,
+    pub heights: Vec<usize>,
+}
+
 impl Crane {
+    pub fn with_model(mut self, model: Box<dyn CraneModel>) -> Self {
+        self.model = model;
+        return self;
+    }
+
+    pub fn step(&mut self) -> Option<&Move> {
+        let index = self.log.len();
+        let instr = self.instructions.get(index)?;
+        let m = apply(self.model.as_ref(), index, &mut self.stacks, instr);
+        self.log.push(m);
+        return self.log.last();
+    }
+
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = self.log.pop()?;
+        let to = &mut self.stacks[m.to - 1];
+        to.truncate(to.len() - m.placed.len());
+        self.stacks[m.from - 1].extend(m.taken.iter().cloned());
+        return Some(m);
+    }
+
+    pub fn run(&mut self) {
+        while self.step().is_some() {}
+    }
+
     pub fn move_per_one(&mut self) {
+        self.model = Box::new(CrateMover9000);
+        self.run();
+    }
+
+    pub fn move_together(&mut self) {
+        self.model = Box::new(CrateMover9001);
+        self.run();
+    }
+
+    // invalid instructions are reported and then skipped for the rest of the dry run
+    pub fn validate(&self) -> Vec<InvalidInstruction> {
+        let mut heights: Vec<usize> = self.initial.iter().map(|s| s.len()).collect();
+        let mut invalid = Vec::new();
         for instr in &self.instructions {
-            for _ in 1..=instr.amount {
-                let c = self.stacks[instr.from - 1].pop_back().unwrap();
-                self.stacks[instr.to - 1].push_back(c);
+            let problem = if let Some(&stack) = [instr.from, instr.to].iter().find(|&&s| s == 0 || s > heights.len()) {
+                Some(Problem::NoSuchStack(stack))
+            } else if instr.amount > heights[instr.from - 1] {
+                Some(Problem::NotEnoughCrates { requested: instr.amount, available: heights[instr.from - 1] })
+            } else {
+                None
+            };
+
+            match problem {
+                Some(problem) => invalid.push(InvalidInstruction {
+                    line: instr.line,
+                    problem,
+                    heights: heights.clone(),
+                }),
+                None => {
+                    heights[instr.from - 1] -= instr.amount;
+                    heights[instr.to - 1] += instr.amount;
+                }
             }
         }
+        return invalid;
     }
 
-    pub fn move_together(&mut self) {
-        for instr in &self.instructions {
-            let from = &mut self.stacks[instr.from - 1];
-            let mut removed = from.split_off(from.len() - instr.amount);
-            self.stacks[instr.to - 1].append(&mut removed);
+    pub fn position(&self) -> usize {
+        return self.log.len();
+    }
+
+    pub fn log(&self) -> &[Move] {
+        return &self.log;
+    }
+
+    pub fn stacks(&self) -> &[Stack] {
+        return &self.stacks;
+    }
+
+    // states replay the recorded moves, so they match what was executed even if the model changed since
+    pub fn state_at(&self, n: usize) -> Option<Vec<Stack>> {
+        return self.states().nth(n);
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = Vec<Stack>> + '_ {
+        let mut stacks = self.initial.clone();
+        let mut moves = self.log.iter();
+        return std::iter::once(stacks.clone())
+            .chain(std::iter::from_fn(move || {
+                let m = moves.next()?;
+                let from = &mut stacks[m.from - 1];
+                from.truncate(from.len() - m.taken.len());
+                stacks[m.to - 1].extend(m.placed.iter().cloned());
+                return Some(stacks.clone());
+            }));
+    }
+
+    // breadth-first, so the first program found is a shortest one; a state is only worth
+    // revisiting when the model will treat the next instruction differently
+    pub fn plan(&self, target: &Target, max_moves: usize) -> Option<Vec<Instruction>> {
+        let mut nodes = vec![PlanNode { stacks: self.stacks.clone(), depth: 0, parent: None }];
+        let period = self.model.period();
+        let mut seen: HashSet<(Vec<Stack>, usize)> = HashSet::new();
+        seen.insert((self.stacks.clone(), 0));
+
+        let mut i = 0;
+        while i < nodes.len() {
+            if target.reached(&nodes[i].stacks) {
+                let mut program = Vec::new();
+                let mut node = i;
+                while let Some((parent, instr)) = &nodes[node].parent {
+                    program.push(instr.clone());
+                    node = *parent;
+                }
+                program.reverse();
+                for (n, instr) in program.iter_mut().enumerate() {
+                    instr.line = n + 1;
+                }
+                return Some(program);
+            }
+
+            let depth = nodes[i].depth;
+            if depth < max_moves {
+                let stacks = nodes[i].stacks.clone();
+                for from in 1..=stacks.len() {
+                    for to in (1..=stacks.len()).filter(|&to| to != from) {
+                        for amount in 1..=stacks[from - 1].len() {
+                            let instr = Instruction { line: 0, amount, from, to };
+                            let mut next = stacks.clone();
+                            apply(self.model.as_ref(), depth, &mut next, &instr);
+                            if seen.insert((next.clone(), (depth + 1) % period)) {
+                                nodes.push(PlanNode { stacks: next, depth: depth + 1, parent: Some((i, instr)) });
+                            }
+                        }
+                    }
+                }
+            }
+            i += 1;
         }
+        return None;
     }
 
     pub fn top(&self) -> String {
         let mut result = String::new();
         for s in &self.stacks {
-            let c = s.back().unwrap();
-            result.push(*c as char);
+            result.push_str(s.back().map_or(" ", |c| c.as_str()));
         }
         return result;
     }
 }
 
 
+pub struct Drawing<'a>(pub &'a [Stack]);
+
+// padding works in chars, so positions in a drawing are char columns rather than byte offsets
+fn column(line: &str, byte: usize) -> usize {
+    return line[..byte].chars().count();
+}
+
+impl fmt::Display for Drawing<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labels: Vec<String> = (1..=self.0.len()).map(|i| i.to_string()).collect();
+        let widths: Vec<usize> = self.0.iter().zip(&labels)
+            .map(|(s, label)| s.iter().map(|c| c.chars().count() + 2).max().unwrap_or(0).max(label.len() + 2))
+            .collect();
+
+        let height = self.0.iter().map(|s| s.len()).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self.0.iter().zip(&widths)
+                .map(|(s, &width)| match s.get(level) {
+                    Some(c) => format!("{:^width$}", format!("[{}]", c)),
+                    None => " ".repeat(width),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let base: Vec<String> = labels.iter().zip(&widths)
+            .map(|(label, &width)| format!("{:^width$}", label))
+            .collect();
+        writeln!(f, "{}", base.join(" "))
+    }
+}
+
+impl fmt::Display for Crane {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Drawing(&self.stacks))
+    }
+}
+
 impl FromStr for Crane {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bottom_crates_re = Regex::new(r"^( \d  ?)*$").unwrap();
+        let bottom_crates_re = Regex::new(r"^\s*\d+(\s+\d+)*\s*$").unwrap();
+        let label_re = Regex::new(r"\d+").unwrap();
+        let crate_re = Regex::new(r"\[([^\]]+)\]").unwrap();
         let instruction_re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
 
-        let num_stacks = (s.lines().nth(0).unwrap().len() + 1) / 4;
-        let mut lines = s.lines();
-        
-        let mut stacks = vec![VecDeque::new(); num_stacks];
-        while let Some(line) = lines.next() {
-            if bottom_crates_re.is_match(line) {
-                break;
-            }
-            for i in 0..num_stacks {
-                let c = line.as_bytes()[i*4 + 1];
-                if c != ' ' as u8 {
-                    stacks[i].push_front(c)
-                }
+        // the numbered base line decides how many stacks there are and where they sit
+        let drawing: Vec<&str> = s.lines().take_while(|l| !bottom_crates_re.is_match(l)).collect();
+        let base = s.lines().nth(drawing.len()).ok_or(())?;
+        let centers: Vec<usize> = label_re.find_iter(base)
+            .map(|m| column(base, m.start()) + column(base, m.end()))
+            .collect();
+        let lines = s.lines().enumerate().skip(drawing.len() + 1);
+
+        let mut stacks = vec![VecDeque::new(); centers.len()];
+        for line in drawing {
+            for m in crate_re.captures_iter(line) {
+                let span = m.get(0).unwrap();
+                let center = column(line, span.start()) + column(line, span.end());
+                let stack = (0..centers.len())
+                    .min_by_key(|&i| centers[i].abs_diff(center))
+                    .ok_or(())?;
+                stacks[stack].push_front(m[1].to_owned());
             }
         }
 
-        // empty line
-        lines.next();
-
         let mut instructions = Vec::new();
-        while let Some(line) = lines.next() {
-            let caps = instruction_re.captures(line).unwrap();
-            let amount = caps[1].parse().unwrap();
-            let from = caps[2].parse().unwrap();
-            let to = caps[3].parse().unwrap();
-            instructions.push(Instruction { amount, from, to });
+        for (n, line) in lines.filter(|(_, l)| !l.trim().is_empty()) {
+            let caps = instruction_re.captures(line).ok_or(())?;
+            let amount = caps[1].parse().map_err(|_| ())?;
+            let from = caps[2].parse().map_err(|_| ())?;
+            let to = caps[3].parse().map_err(|_| ())?;
+            instructions.push(Instruction { line: n + 1, amount, from, to });
         }
 
         return Ok(Crane {
+            initial: stacks.clone(),
             stacks,
-            instructions
+            instructions,
+            model: Box::new(CrateMover9000),
+            log: vec![],
         });
     }
 }
 
+pub type Outcome = Result<String, Vec<InvalidInstruction>>;
+
+// each model reports its top crates, or the instructions that keep the program from running;
+// `None` when the input can't be parsed
+pub fn compare(input: &str, models: Vec<Box<dyn CraneModel>>) -> Option<Vec<(String, Outcome)>> {
+    let mut results = Vec::new();
+    for model in models {
+        let name = model.name();
+        let mut c: Crane = input.parse::<Crane>().ok()?.with_model(model);
+        let invalid = c.validate();
+        if invalid.is_empty() {
+            c.run();
+            results.push((name, Ok(c.top())));
+        } else {
+            results.push((name, Err(invalid)));
+        }
+    }
+    return Some(results);
+}
+
 fn main() {
     let mut input = String::new();
 
@@ -96,9 +430,23 @@ fn main() {
         .read_to_string(&mut input)
         .expect("Failed to read input");
 
-    let mut c: Crane = input.parse().unwrap();
-    c.move_together();
-    println!("{}", c.top());
+    let names: Vec<String> = env::args().skip(1).collect();
+    if names.is_empty() {
+        let mut c: Crane = input.parse().unwrap();
+        c.move_together();
+        println!("{}", c.top());
+        return;
+    }
+
+    let models = names.iter()
+        .map(|n| model(n).unwrap_or_else(|| panic!("Unknown crane model {}", n)))
+        .collect();
+    for (name, result) in compare(&input, models).expect("Failed to parse input") {
+        match result {
+            Ok(top) => println!("{:<16} {}", name, top),
+            Err(invalid) => println!("{:<16} invalid instruction on line {}", name, invalid[0].line),
+        }
+    }
 }
 
 
@@ -119,6 +467,10 @@ mod tests {
         move 1 from 1 to 2
     "};
 
+    fn crates(names: &str) -> Vec<Crate> {
+        return names.chars().map(|c| c.to_string()).collect();
+    }
+
     #[test]
     fn test_move_per_one() {
         let mut c: Crane = INPUT.parse().unwrap();
@@ -133,4 +485,200 @@ mod tests {
         assert_eq!(c.top(), "MCD");
     }
 
+    #[test]
+    fn test_step_and_undo() {
+        let mut c: Crane = INPUT.parse().unwrap();
+        let m = c.step().unwrap();
+        assert_eq!((m.from, m.to, m.taken.clone()), (2, 1, crates("D")));
+        let m = c.step().unwrap();
+        assert_eq!((m.taken.clone(), m.placed.clone()), (crates("ZND"), crates("DNZ")));
+        c.undo();
+        assert_eq!(c.position(), 1);
+        assert_eq!(c.stacks(), c.state_at(1).unwrap());
+        assert_eq!(c.state_at(2), None);
+        c.run();
+        assert_eq!(c.top(), "CMZ");
+        assert!(c.step().is_none());
+        while c.undo().is_some() {}
+        assert_eq!(c.stacks(), c.state_at(0).unwrap());
+
+        let mut c = c.with_model(Box::new(CrateMover9001));
+        c.run();
+        let tops: Vec<String> = c.states()
+            .map(|s| s.iter().map(|s| s.back().map_or(" ", |c| c.as_str())).collect())
+            .collect();
+        assert_eq!(tops, vec!["NDP", "DCP", " CD", "C D", "MCD"]);
+    }
+
+    #[test]
+    fn test_states_after_model_change() {
+        let mut c: Crane = INPUT.parse().unwrap();
+        c.step();
+        c.step();
+        let mut c = c.with_model(Box::new(CrateMover9001));
+        c.run();
+        assert_eq!(c.state_at(2).unwrap()[2], crates("PDNZ"));
+        assert_eq!(c.states().last().unwrap(), c.stacks());
+        assert_eq!(c.state_at(5), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let c: Crane = INPUT.parse().unwrap();
+        assert!(c.validate().is_empty());
+
+        let c: Crane = indoc! {"
+                [D]    
+            [N] [C]    
+            [Z] [M] [P]
+             1   2   3 
+
+            move 1 from 2 to 1
+            move 4 from 1 to 3
+            move 1 from 4 to 1
+            move 2 from 3 to 0
+            move 2 from 1 to 3
+        "}.parse().unwrap();
+        assert_eq!(c.validate(), vec![
+            InvalidInstruction {
+                line: 7,
+                problem: Problem::NotEnoughCrates { requested: 4, available: 3 },
+                heights: vec![3, 2, 1],
+            },
+            InvalidInstruction { line: 8, problem: Problem::NoSuchStack(4), heights: vec![3, 2, 1] },
+            InvalidInstruction { line: 9, problem: Problem::NoSuchStack(0), heights: vec![3, 2, 1] },
+        ]);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut c: Crane = INPUT.parse().unwrap();
+        let drawing = c.to_string();
+        assert_eq!(drawing, INPUT.split("\n\n").next().unwrap().to_owned() + "\n");
+        let round_trip: Crane = drawing.parse().unwrap();
+        assert_eq!(round_trip.stacks(), c.stacks());
+
+        c.move_together();
+        assert_eq!(c.to_string(), indoc! {"
+                    [D]
+                    [N]
+                    [Z]
+            [M] [C] [P]
+             1   2   3 
+        "});
+        let state = c.state_at(2).unwrap();
+        assert_eq!(Drawing(&state).to_string().parse::<Crane>().unwrap().stacks(), state);
+    }
+
+    #[test]
+    fn test_models() {
+        assert_eq!(Capped(2).place(0, &crates("abcde")), crates("debca"));
+        let models = ["9000", "9001", "capped:1", "capped:2", "alternating"].iter()
+            .map(|n| model(n).unwrap())
+            .collect();
+        assert_eq!(compare(INPUT, models), Some(vec![
+            ("CrateMover 9000".to_owned(), Ok("CMZ".to_owned())),
+            ("CrateMover 9001".to_owned(), Ok("MCD".to_owned())),
+            ("capped at 1".to_owned(), Ok("CMZ".to_owned())),
+            ("capped at 2".to_owned(), Ok("MCZ".to_owned())),
+            ("alternating".to_owned(), Ok("CMD".to_owned())),
+        ]));
+
+        let emptied = INPUT.replace("move 1 from 1 to 2", "move 2 from 1 to 2");
+        let models = vec![model("9000").unwrap(), model("alternating").unwrap()];
+        assert_eq!(compare(&emptied, models), Some(vec![
+            ("CrateMover 9000".to_owned(), Ok(" CZ".to_owned())),
+            ("alternating".to_owned(), Ok(" MD".to_owned())),
+        ]));
+        let invalid = INPUT.replace("move 3 from 1 to 3", "move 4 from 1 to 3");
+        let results = compare(&invalid, vec![model("capped:2").unwrap()]).unwrap();
+        assert_eq!(results[0].1.as_ref().unwrap_err()[0].line, 7);
+        assert!(compare("no drawing here", vec![model("9000").unwrap()]).is_none());
+        assert!(model("capped:0").is_none());
+        assert!(model("9002").is_none());
+    }
+
+    #[test]
+    fn test_plan() {
+        let c: Crane = INPUT.parse().unwrap();
+        let program = c.plan(&Target::Top("CMZ".to_owned()), 4).unwrap();
+        assert_eq!(program.len(), 2);
+        let text: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        let mut replay: Crane = (c.to_string() + "\n" + &text.join("\n")).parse().unwrap();
+        replay.run();
+        assert_eq!(replay.top(), "CMZ");
+
+        let mut done: Crane = INPUT.parse::<Crane>().unwrap().with_model(Box::new(CrateMover9001));
+        done.run();
+        let c = c.with_model(Box::new(CrateMover9001));
+        let program = c.plan(&Target::Arrangement(done.stacks().to_vec()), 4).unwrap();
+        assert!(program.len() <= 4);
+        assert_eq!(program[0].line, 1);
+
+        assert!(c.plan(&Target::Top("XYZ".to_owned()), 3).is_none());
+    }
+
+    #[test]
+    fn test_plan_alternating() {
+        let drawing = indoc! {"
+            [D]    
+            [C]    
+            [B]    
+            [A]    
+             1   2 
+        "};
+        let c: Crane = drawing.parse::<Crane>().unwrap().with_model(Box::new(Alternating));
+        // the shortest program goes through a state that is first reached at the other phase
+        let target = Target::Arrangement(vec![crates("BADC").into(), Stack::new()]);
+        let program = c.plan(&target, 4).unwrap();
+        assert_eq!(program.len(), 4);
+        let text: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        let mut replay = (drawing.to_owned() + "\n" + &text.join("\n")).parse::<Crane>().unwrap()
+            .with_model(Box::new(Alternating));
+        replay.run();
+        assert!(target.reached(replay.stacks()));
+    }
+
+    #[test]
+    fn test_irregular_drawing() {
+        let ragged = INPUT.lines().map(|l| l.trim_end()).collect::<Vec<_>>().join("\n");
+        let mut c: Crane = ragged.parse().unwrap();
+        c.move_per_one();
+        assert_eq!(c.top(), "CMZ");
+
+        let wide = indoc! {"
+            [A]                                     [K]
+            [B] [C] [D] [E] [F] [G] [H] [I] [J] [XY] [L]
+             1   2   3   4   5   6   7   8   9   10   11
+
+            move 1 from 11 to 10
+            move 1 from 10 to 1
+        "};
+        let mut c: Crane = wide.parse().unwrap();
+        assert_eq!(c.stacks().len(), 11);
+        assert_eq!(c.stacks()[9], VecDeque::from(vec!["XY".to_owned()]));
+        c.move_together();
+        assert_eq!(c.top(), "KCDEFGHIJXYL");
+        assert_eq!(c.stacks()[0], VecDeque::from(crates("BAK")));
+
+        let round_trip: Crane = c.to_string().parse().unwrap();
+        assert_eq!(round_trip.stacks(), c.stacks());
+        assert!("no drawing here".parse::<Crane>().is_err());
+        let drawing = INPUT.split("\n\n").next().unwrap().to_owned() + "\n\n";
+        assert!((drawing.clone() + "move 1 from 2 to 1 please").parse::<Crane>().is_err());
+        assert!((drawing.clone() + "move one from 2 to 1").parse::<Crane>().is_err());
+        assert!((drawing + "move 99999999999999999999 from 2 to 1").parse::<Crane>().is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_drawing() {
+        let stacks: Vec<Stack> = vec![crates("éü").into(), crates("ö").into(), crates("ñßç").into(), crates("å").into(), crates("ø").into()];
+        let round_trip: Crane = Drawing(&stacks).to_string().parse().unwrap();
+        assert_eq!(round_trip.stacks(), stacks);
+
+        let stacks: Vec<Stack> = vec![vec!["日本".to_owned()].into(), Stack::new(), crates("x").into()];
+        let round_trip: Crane = Drawing(&stacks).to_string().parse().unwrap();
+        assert_eq!(round_trip.stacks(), stacks);
+    }
+
 }
\ No newline at end of file
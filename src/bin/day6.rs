@@ -1,34 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 use std::io;
 use std::io::Read;
 
-pub fn all_different<T: PartialEq>(input: &[T]) -> bool {
-    for i in 0..input.len() {
-        for j in (i + 1)..input.len() {
-            if input[i] == input[j] {
-                return false;
+// first offset whose last `len` items are all different; the count table keeps each step O(1)
+pub fn marker<T: Eq + Hash + Clone>(input: &[T], len: usize) -> Option<usize> {
+    let mut detector = MarkerDetector::new(len);
+    return input.iter().find_map(|item| detector.push(item.clone()));
+}
+
+// Reports the offset just past each window of `len` different items. After a marker the
+// window starts over, so consecutive markers never share items.
+pub struct MarkerDetector<T> {
+    len: usize,
+    counts: HashMap<T, usize>,
+    window: VecDeque<T>,
+    offset: usize,
+}
+
+impl<T: Eq + Hash + Clone> MarkerDetector<T> {
+    pub fn new(len: usize) -> Self {
+        return MarkerDetector { len, counts: HashMap::new(), window: VecDeque::new(), offset: 0 };
+    }
+
+    pub fn push(&mut self, item: T) -> Option<usize> {
+        self.offset += 1;
+        self.window.push_back(item.clone());
+        *self.counts.entry(item).or_insert(0) += 1;
+        if self.window.len() > self.len {
+            let old = self.window.pop_front().unwrap();
+            let count = self.counts.get_mut(&old).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&old);
             }
         }
+        if self.counts.len() < self.len {
+            return None;
+        }
+        self.window.clear();
+        self.counts.clear();
+        return Some(self.offset);
+    }
+
+    pub fn feed(&mut self, chunk: &[T]) -> Vec<usize> {
+        return chunk.iter().filter_map(|item| self.push(item.clone())).collect();
     }
-    return true;
 }
 
-pub fn different(input: &str, len: usize) -> usize {
-    return len + 
-        input.as_bytes().windows(len).enumerate()
-        .filter(|(_, s)| all_different(s))
-        .map(|(i, _)| i)
-        .nth(0)
-        .unwrap();
+pub fn byte_marker(input: &[u8], len: usize) -> Option<usize> {
+    return marker(input, len);
 }
 
-fn main() {
-    let mut input = String::new();
+pub fn different(input: &str, len: usize) -> Option<usize> {
+    return byte_marker(input.as_bytes(), len);
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Marker {
+    Packet(usize),
+    Message(usize),
+}
+
+// every packet and message marker in the stream; each kind restarts its window after a
+// marker, so markers of the same kind never overlap
+pub fn markers<R: Read>(mut reader: R, packet_len: usize, message_len: usize) -> io::Result<Vec<Marker>> {
+    let mut packets = MarkerDetector::new(packet_len);
+    let mut messages = MarkerDetector::new(message_len);
+    let mut found = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(found);
+        }
+        for &b in &buf[..n] {
+            if let Some(offset) = packets.push(b) {
+                found.push(Marker::Packet(offset));
+            }
+            if let Some(offset) = messages.push(b) {
+                found.push(Marker::Message(offset));
+            }
+        }
+    }
+}
+
+pub fn first_marker<R: Read>(mut reader: R, len: usize) -> io::Result<Option<usize>> {
+    let mut detector = MarkerDetector::new(len);
+    let mut buf = [0; 4096];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        if let Some(offset) = buf[..n].iter().find_map(|&b| detector.push(b)) {
+            return Ok(Some(offset));
+        }
+    }
+}
 
-    io::stdin()
-        .read_to_string(&mut input)
-        .expect("Failed to read input");
+// A packet starts after each start-of-packet marker and each start-of-message marker inside
+// it starts a message; each record is a message offset and its payload. Bytes before the
+// first message of a packet are a header and are not reported, so a packet without any
+// message yields no record. Packet markers overlapping a message marker don't count, so
+// payloads must avoid both patterns.
+pub fn frames(input: &[u8], packet_len: usize, message_len: usize) -> Frames<'_> {
+    return Frames {
+        input,
+        pos: 0,
+        packet_len,
+        message_len,
+        packets: MarkerDetector::new(packet_len),
+        messages: None,
+        pending: VecDeque::new(),
+        message: None,
+    };
+}
 
-    println!("{}", different(&input, 14));
+// a single pass over the input: both detectors see every byte once
+pub struct Frames<'a> {
+    input: &'a [u8],
+    pos: usize,
+    packet_len: usize,
+    message_len: usize,
+    packets: MarkerDetector<u8>,
+    // only starts once the first packet has been found
+    messages: Option<MarkerDetector<u8>>,
+    // packet markers that a later message marker could still overlap
+    pending: VecDeque<usize>,
+    message: Option<usize>,
+}
+
+impl<'a> Frames<'a> {
+    fn close_packet(&mut self, packet_end: usize) -> Option<(usize, &'a [u8])> {
+        let start = self.message.take()?;
+        return Some((start, &self.input[start..packet_end - self.packet_len]));
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.input.len() {
+            let b = self.input[self.pos];
+            self.pos += 1;
+            let Some(messages) = &mut self.messages else {
+                if self.packets.push(b).is_some() {
+                    self.messages = Some(MarkerDetector::new(self.message_len));
+                }
+                continue;
+            };
+
+            // detectors restart after every marker, so the current position is the absolute offset
+            if self.packets.push(b).is_some() {
+                self.pending.push_back(self.pos);
+            }
+            if messages.push(b).is_some() {
+                let message_end = self.pos;
+                self.pending.clear();
+                self.packets = MarkerDetector::new(self.packet_len);
+                if let Some(start) = self.message.replace(message_end) {
+                    return Some((start, &self.input[start..message_end - self.message_len]));
+                }
+            } else if self.pending.front().is_some_and(|&p| self.pos + 1 >= p + self.message_len) {
+                let packet_end = self.pending.pop_front().unwrap();
+                if let Some(record) = self.close_packet(packet_end) {
+                    return Some(record);
+                }
+            }
+        }
+
+        // nothing can overlap the remaining packet markers any more
+        while let Some(packet_end) = self.pending.pop_front() {
+            if let Some(record) = self.close_packet(packet_end) {
+                return Some(record);
+            }
+        }
+        let start = self.message.take()?;
+        return Some((start, &self.input[start..]));
+    }
+}
+
+fn main() {
+    match first_marker(io::stdin(), 14).expect("Failed to read input") {
+        Some(offset) => println!("{}", offset),
+        None => println!("No marker found"),
+    }
 }
 
 
@@ -38,20 +196,71 @@ mod tests {
 
     #[test]
     fn test_four_different() {
-        assert_eq!(different("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7);
-        assert_eq!(different("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5);
-        assert_eq!(different("nppdvjthqldpwncqszvftbrmjlhg", 4), 6);
-        assert_eq!(different("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), 10);
-        assert_eq!(different("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
+        assert_eq!(different("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), Some(7));
+        assert_eq!(different("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), Some(5));
+        assert_eq!(different("nppdvjthqldpwncqszvftbrmjlhg", 4), Some(6));
+        assert_eq!(different("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), Some(10));
+        assert_eq!(different("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), Some(11));
     }
 
     #[test]
     fn test_fourteen_different() {
-        assert_eq!(different("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
-        assert_eq!(different("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), 23);
-        assert_eq!(different("nppdvjthqldpwncqszvftbrmjlhg", 14), 23);
-        assert_eq!(different("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), 29);
-        assert_eq!(different("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), 26);
+        assert_eq!(different("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), Some(19));
+        assert_eq!(different("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), Some(23));
+        assert_eq!(different("nppdvjthqldpwncqszvftbrmjlhg", 14), Some(23));
+        assert_eq!(different("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), Some(29));
+        assert_eq!(different("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), Some(26));
+    }
+
+    #[test]
+    fn test_marker() {
+        let stream = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg";
+        let chars: Vec<char> = stream.chars().collect();
+        assert_eq!(marker(&chars, 4), Some(10));
+        assert_eq!(marker(&chars, 14), Some(29));
+        assert_eq!(marker(&chars, 40), None);
+        assert_eq!(byte_marker(b"aaaa", 2), None);
+
+        let long: Vec<u32> = (0..5000).map(|i| i % 3000).collect();
+        assert_eq!(marker(&long, 3000), Some(3000));
+        assert_eq!(marker(&long[1..], 3000), Some(3000));
+        assert_eq!(marker(&long, 3001), None);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let mut detector = MarkerDetector::new(4);
+        assert_eq!(detector.feed(b"mjqjpq"), vec![]);
+        assert_eq!(detector.feed(b"mgbljsph"), vec![7, 11]);
+        assert_eq!(different("abab", 4), None);
+        assert_eq!(MarkerDetector::new(2).feed(&['a', 'a', 'b', 'c', 'd']), vec![3, 5]);
+
+        let stream = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes();
+        assert_eq!(first_marker(stream, 14).unwrap(), Some(19));
+        assert_eq!(first_marker(stream, 27).unwrap(), None);
+        let found = markers(stream, 4, 14).unwrap();
+        assert_eq!(found[..3], [Marker::Packet(7), Marker::Packet(11), Marker::Packet(15)]);
+        assert!(found.contains(&Marker::Message(19)));
+    }
+
+    #[test]
+    fn test_frames() {
+        let stream = [
+            "abcd", "ee",
+            "efghijklmnopqr", "sstsEE",
+            "EFGHIJKLMNOPQR", "uuvuw",
+            "wxyz", "A",
+            "ABCDEFGHIJKLMN", "ooo",
+        ].concat();
+        let records: Vec<(usize, &[u8])> = frames(stream.as_bytes(), 4, 14).collect();
+        assert_eq!(records, vec![
+            (20, "sstsEE".as_bytes()),
+            (40, "uuvuw".as_bytes()),
+            (64, "ooo".as_bytes()),
+        ]);
+        assert_eq!(frames(b"aaaa", 4, 14).count(), 0);
+        // header bytes and packets without a message produce no records
+        assert_eq!(frames(b"abcdbbwxyzzmnopqrss", 4, 6).collect::<Vec<_>>(), vec![(16, "rss".as_bytes())]);
     }
 
 }
\ No newline at end of file
@@ -1,100 +1,525 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fmt::Write;
+use std::fs;
 use std::io;
 use std::io::Read;
+use std::path::Path;
 use std::str::Lines;
 
 use regex::Regex;
 
 #[derive(Debug)]
 pub struct Directory {
+    name: String,
+    path: String,
     files: Vec<File>,
-    dirs: HashMap<String, Directory>
+    dirs: BTreeMap<String, Directory>,
+    // cached total size, filled in by `update_sizes` once the tree is built
+    size: i64,
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent == "/" {
+        return format!("/{}", name);
+    }
+    return format!("{}/{}", parent, name);
 }
 
 impl Directory {
     pub fn empty() -> Self {
-        return Directory { files: vec![], dirs: HashMap::new() }
+        return Directory::named("/", "/");
+    }
+
+    fn named(name: &str, path: &str) -> Self {
+        return Directory { name: name.to_owned(), path: path.to_owned(), files: vec![], dirs: BTreeMap::new(), size: 0 }
     }
 
     pub fn from_commands(input: &mut Lines) -> Self {
-        let mut dirs = HashMap::new();
-        let mut files = Vec::new();
-        let cd_in = Regex::new(r"^\$ cd ([a-z]+)$").unwrap();
-        let cd_out = Regex::new(r"^\$ cd \.\.$").unwrap();
+        return Directory::interpret(input).0;
+    }
+
+    // takes the whole transcript so reported line numbers count from its first line
+    pub fn from_transcript(input: &str) -> (Self, Vec<Inconsistency>) {
+        return Directory::interpret(input.lines());
+    }
+
+    fn interpret<'a>(input: impl Iterator<Item = &'a str>) -> (Self, Vec<Inconsistency>) {
+        let cd_re = Regex::new(r"^\$ cd (.+)$").unwrap();
         let ls_re = Regex::new(r"^\$ ls$").unwrap();
-        let dir_re = Regex::new(r"^dir ([a-z]+)$").unwrap();
-        let file_re = Regex::new(r"^(\d+) ([a-z.]+)$").unwrap();
+        let dir_re = Regex::new(r"^dir (.+)$").unwrap();
+        let file_re = Regex::new(r"^(\d+) (.+)$").unwrap();
+
+        let mut root = Directory::empty();
+        let mut problems = Vec::new();
+        let mut cwd: Vec<String> = Vec::new();
+        // line of the `ls` currently being read and the names it listed, to check against earlier listings
+        let mut listing: Option<(usize, HashSet<String>)> = None;
+
+        for (n, line) in input.enumerate() {
+            let line_no = n + 1;
+            if line.starts_with('$') {
+                if let Some((ls_line, seen)) = listing.take() {
+                    root.dir_mut(&cwd).check_listing(&seen, ls_line, &mut problems);
+                }
+            }
 
-        while let Some(line) = input.next() {
             if ls_re.is_match(line) {
+                listing = Some((line_no, HashSet::new()));
+            } else if let Some(target) = cd_re.captures(line) {
+                match root.change_dir(&cwd, &target[1], line_no) {
+                    Ok(path) => cwd = path,
+                    Err(problem) => problems.push(problem),
+                }
             } else if let Some(new_dir) = dir_re.captures(line) {
-                dirs.insert(new_dir[1].to_owned(), Directory::empty());
+                let dir = root.dir_mut(&cwd);
+                if dir.add_dir(&new_dir[1]).is_err() {
+                    problems.push(Inconsistency::KindMismatch { line: line_no, path: join(&dir.path, &new_dir[1]) });
+                }
+                listing.iter_mut().for_each(|(_, seen)| { seen.insert(new_dir[1].to_owned()); });
             } else if let Some(new_file) = file_re.captures(line) {
-                files.push(File {
-                    size: new_file[1].parse().unwrap(),
-                });
-            } else if let Some(sub_dir) = cd_in.captures(line) {
-                let created_dir = Directory::from_commands(input);
-                *dirs.get_mut(&sub_dir[1]).unwrap() = created_dir;
-            } else if cd_out.is_match(line) {
-                break;
+                let dir = root.dir_mut(&cwd);
+                match new_file[1].parse() {
+                    Ok(size) => problems.extend(dir.add_file(&new_file[2], size, line_no)),
+                    Err(_) => problems.push(Inconsistency::InvalidSize { line: line_no, path: join(&dir.path, &new_file[2]) }),
+                }
+                listing.iter_mut().for_each(|(_, seen)| { seen.insert(new_file[2].to_owned()); });
             } else {
-                panic!("Unknown line {}", line);
+                problems.push(Inconsistency::UnknownLine { line: line_no, text: line.to_owned() });
+            }
+        }
+        if let Some((ls_line, seen)) = listing {
+            root.dir_mut(&cwd).check_listing(&seen, ls_line, &mut problems);
+        }
+        root.update_sizes();
+        return (root, problems);
+    }
+
+    // `target` may be absolute and have several segments; it is resolved before any directory is
+    // created, and on a problem the old cwd stays
+    fn change_dir(&mut self, cwd: &[String], target: &str, line: usize) -> Result<Vec<String>, Inconsistency> {
+        let mut path: Vec<&str> = if target.starts_with('/') { vec![] } else { cwd.iter().map(|s| s.as_str()).collect() };
+        for name in target.split('/').filter(|&name| !name.is_empty() && name != ".") {
+            if name == ".." {
+                path.pop().ok_or(Inconsistency::AboveRoot { line })?;
+            } else {
+                path.push(name);
+            }
+        }
+
+        let mut existing = Some(&*self);
+        for name in &path {
+            let Some(dir) = existing else { break };
+            if dir.files.iter().any(|f| f.name == *name) {
+                return Err(Inconsistency::KindMismatch { line, path: join(&dir.path, name) });
+            }
+            existing = dir.dirs.get(*name);
+        }
+        let mut dir = self;
+        for name in &path {
+            dir.add_dir(name).unwrap();
+            dir = dir.dirs.get_mut(*name).unwrap();
+        }
+        return Ok(path.into_iter().map(|s| s.to_owned()).collect());
+    }
+
+    fn dir_mut(&mut self, path: &[String]) -> &mut Directory {
+        let mut dir = self;
+        for name in path {
+            dir = dir.dirs.get_mut(name).unwrap();
+        }
+        return dir;
+    }
+
+    fn add_dir(&mut self, name: &str) -> Result<(), ()> {
+        if self.files.iter().any(|f| f.name == name) {
+            return Err(());
+        }
+        let path = join(&self.path, name);
+        self.dirs.entry(name.to_owned()).or_insert_with(|| Directory::named(name, &path));
+        return Ok(());
+    }
+
+    fn add_file(&mut self, name: &str, size: i64, line: usize) -> Option<Inconsistency> {
+        let path = join(&self.path, name);
+        if self.dirs.contains_key(name) {
+            return Some(Inconsistency::KindMismatch { line, path });
+        }
+        return match self.files.iter().find(|f| f.name == name) {
+            Some(f) if f.size != size => Some(Inconsistency::SizeMismatch { line, path, listed: f.size, now: size }),
+            Some(_) => None,
+            None => {
+                self.files.push(File { name: name.to_owned(), size });
+                None
             }
-            
+        };
+    }
+
+    fn check_listing(&self, seen: &HashSet<String>, line: usize, problems: &mut Vec<Inconsistency>) {
+        let known = self.files.iter().map(|f| &f.name).chain(self.dirs.keys());
+        for name in known.filter(|name| !seen.contains(*name)) {
+            problems.push(Inconsistency::MissingFromListing { line, path: join(&self.path, name) });
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn path(&self) -> &str {
+        return &self.path;
+    }
+
+    pub fn files(&self) -> &[File] {
+        return &self.files;
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Directory> {
+        let mut dir = self;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            dir = dir.dirs.get(name)?;
+        }
+        return Some(dir);
+    }
+
+    pub fn parent_path(&self) -> Option<&str> {
+        if self.path == "/" {
+            return None;
         }
-        return Directory { 
-            files,
-            dirs,
+        return match self.path.rfind('/')? {
+            0 => Some("/"),
+            i => Some(&self.path[..i]),
         };
     }
 
-    pub fn sum_of_small_dirs(&self) -> i32 {
-        return self.dirs().iter()
+    pub fn parent(&self, dir: &Directory) -> Option<&Directory> {
+        return self.get(dir.parent_path()?);
+    }
+
+    pub fn entries(&self) -> Vec<(String, i64)> {
+        let mut result = vec![(self.path.clone(), self.total_size())];
+        for f in &self.files {
+            result.push((join(&self.path, &f.name), f.size));
+        }
+        for d in self.dirs.values() {
+            result.append(&mut d.entries());
+        }
+        return result;
+    }
+
+    pub fn small_dirs(&self) -> Vec<&Directory> {
+        return self.dirs()
             .filter(|d| d.total_size() < 100000)
+            .collect();
+    }
+
+    pub fn sum_of_small_dirs(&self) -> i64 {
+        return self.small_dirs().iter()
             .map(|d| d.total_size())
             .sum();
     }
     
-    pub fn dir_with_enough_space(&self) -> i32 {
-        let unused_size = 70000000 - self.total_size();
-        let needed_size = 30000000 - unused_size;
-        let mut okay_dirs: Vec<&Directory> = self.dirs().into_iter()
-            .filter(|d| d.total_size() > needed_size)
-            .collect();
-        okay_dirs.sort_by(|a, b| a.total_size().cmp(&b.total_size()));
-        return okay_dirs.get(0).unwrap().total_size();
+    pub fn dir_with_enough_space(&self) -> i64 {
+        // with enough space already free, every directory qualifies and the smallest is picked
+        let needed_size = self.space_to_free(70000000, 30000000);
+        return self.dirs()
+            .map(|d| d.total_size())
+            .filter(|&size| size >= needed_size)
+            .min()
+            .unwrap_or(0);
     }
 
-    pub fn dirs(&self) -> Vec<&Directory> {
-        let mut result = Vec::new();
-        for (_, d) in &self.dirs {
-            result.push(d);
-            result.append(&mut d.dirs());
+    pub fn space_to_free(&self, capacity: i64, required: i64) -> i64 {
+        let unused_size = capacity - self.total_size();
+        return required - unused_size;
+    }
+
+    pub fn dir_to_delete(&self, capacity: i64, required: i64) -> Option<&Directory> {
+        let needed_size = self.space_to_free(capacity, required);
+        if needed_size <= 0 {
+            return None;
         }
-        return result;
+        return self.dirs()
+            .filter(|d| d.total_size() >= needed_size)
+            .min_by_key(|d| d.total_size());
+    }
+
+    // Fewest directories that free enough space together, and of those the least data. Picking
+    // the least data is a subset-sum problem, so each size of set gets `SEARCH_STEPS` steps; a
+    // search cut short keeps the best set found so far, which may free more than necessary.
+    pub fn dirs_to_delete(&self, capacity: i64, required: i64) -> Option<Vec<&Directory>> {
+        let needed_size = self.space_to_free(capacity, required);
+        if needed_size <= 0 {
+            return Some(vec![]);
+        }
+        let mut candidates: Vec<&Directory> = self.dirs().collect();
+        candidates.sort_by_key(|d| -d.total_size());
+
+        for k in 1..=candidates.len() {
+            let mut best = None;
+            let mut steps = SEARCH_STEPS;
+            pick_dirs(&candidates, k, needed_size, &mut vec![], 0, &mut best, &mut steps);
+            if best.is_some() {
+                return best.map(|(_, dirs)| dirs);
+            }
+        }
+        return None;
     }
 
-    pub fn total_size(&self) -> i32 {
-        let subdirs: i32 = self.dirs.iter().map(|(_, d)| d.total_size()).sum();
-        let files: i32 = self.files.iter().map(|f| f.size).sum();
-        return subdirs + files;
+    pub fn dirs(&self) -> Dirs<'_> {
+        return Dirs { stack: self.dirs.values().rev().collect() };
     }
+
+    fn update_sizes(&mut self) -> i64 {
+        let subdirs: i64 = self.dirs.values_mut().map(|d| d.update_sizes()).sum();
+        let files: i64 = self.files.iter().map(|f| f.size).sum();
+        self.size = subdirs + files;
+        return self.size;
+    }
+
+    pub fn total_size(&self) -> i64 {
+        return self.size;
+    }
+}
+
+const SEARCH_STEPS: usize = 100000;
+
+fn nested(a: &Directory, b: &Directory) -> bool {
+    let inside = |outer: &Directory, inner: &Directory| inner.path.starts_with(&outer.path)
+        && inner.path[outer.path.len()..].starts_with('/');
+    return inside(a, b) || inside(b, a);
+}
+
+// Branch and bound over candidates sorted largest first: the next few candidates bound what
+// the remaining picks can add, and the last few bound the least they must add.
+fn pick_dirs<'a>(
+    candidates: &[&'a Directory],
+    k: usize,
+    needed: i64,
+    picked: &mut Vec<&'a Directory>,
+    total: i64,
+    best: &mut Option<(i64, Vec<&'a Directory>)>,
+    steps: &mut usize,
+) {
+    if *steps == 0 {
+        return;
+    }
+    *steps -= 1;
+    if picked.len() == k {
+        if total >= needed && !matches!(best, Some((t, _)) if *t <= total) {
+            *best = Some((total, picked.clone()));
+        }
+        return;
+    }
+    let remaining = k - picked.len();
+    if candidates.len() < remaining {
+        return;
+    }
+    // no completion can end below this, and no set of k below `needed`
+    let least: i64 = candidates.iter().rev().take(remaining).map(|d| d.total_size()).sum();
+    if matches!(best, Some((t, _)) if *t <= needed.max(total + least)) {
+        return;
+    }
+    for (i, d) in candidates.iter().enumerate() {
+        let most: i64 = candidates[i..].iter().take(remaining).map(|d| d.total_size()).sum();
+        if total + most < needed {
+            break;
+        }
+        if picked.iter().any(|p| nested(p, d)) {
+            continue;
+        }
+        picked.push(d);
+        pick_dirs(&candidates[i + 1..], k, needed, picked, total + d.total_size(), best, steps);
+        picked.pop();
+    }
+}
+
+// pre-order walk over all subdirectories
+pub struct Dirs<'a> {
+    stack: Vec<&'a Directory>,
+}
+
+impl<'a> Iterator for Dirs<'a> {
+    type Item = &'a Directory;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = self.stack.pop()?;
+        self.stack.extend(d.dirs.values().rev());
+        return Some(d);
+    }
+}
+
+// `line` counts from 1; a missing entry is reported at the `$ ls` that left it out
+#[derive(Debug, PartialEq)]
+pub enum Inconsistency {
+    SizeMismatch { line: usize, path: String, listed: i64, now: i64 },
+    KindMismatch { line: usize, path: String },
+    MissingFromListing { line: usize, path: String },
+    AboveRoot { line: usize },
+    InvalidSize { line: usize, path: String },
+    UnknownLine { line: usize, text: String },
 }
 
 #[derive(Debug)]
 pub struct File {
-    size: i32,
+    name: String,
+    size: i64,
+}
+
+impl File {
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn size(&self) -> i64 {
+        return self.size;
+    }
+}
+
+pub fn human_size(size: i64) -> String {
+    let mut value = size as f64;
+    let mut unit = "";
+    for next in ["K", "M", "G", "T"] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    // like du, round up so a size is never under-reported
+    if unit.is_empty() || value >= 10.0 {
+        return format!("{}{}", value.ceil(), unit);
+    }
+    return format!("{:.1}{}", (value * 10.0).ceil() / 10.0, unit);
+}
+
+fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(result, "\\u{:04x}", c as u32).unwrap(),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    return result;
+}
+
+enum Node<'a> {
+    Dir(&'a Directory),
+    File(&'a File),
+}
+
+impl Directory {
+    fn children(&self) -> Vec<(&str, Node<'_>)> {
+        let mut children: Vec<(&str, Node)> = self.dirs.values().map(|d| (d.name(), Node::Dir(d)))
+            .chain(self.files.iter().map(|f| (f.name(), Node::File(f))))
+            .collect();
+        children.sort_by_key(|(name, _)| *name);
+        return children;
+    }
+
+    pub fn tree(&self) -> String {
+        fn render(dir: &Directory, depth: usize, out: &mut String) {
+            writeln!(out, "{}- {} (dir, size={})", "  ".repeat(depth), dir.name, dir.size).unwrap();
+            for (name, child) in dir.children() {
+                match child {
+                    Node::Dir(d) => render(d, depth + 1, out),
+                    Node::File(f) => writeln!(out, "{}- {} (file, size={})", "  ".repeat(depth + 1), name, f.size).unwrap(),
+                }
+            }
+        }
+        let mut out = String::new();
+        render(self, 0, &mut out);
+        return out;
+    }
+
+    pub fn du(&self) -> String {
+        let mut dirs: Vec<&Directory> = std::iter::once(self).chain(self.dirs()).collect();
+        dirs.sort_by_key(|d| (-d.size, d.path.clone()));
+        let mut out = String::new();
+        for d in dirs {
+            writeln!(out, "{}\t{}", human_size(d.size), d.path).unwrap();
+        }
+        return out;
+    }
+
+    pub fn to_json(&self) -> String {
+        let children: Vec<String> = self.children().into_iter()
+            .map(|(name, child)| match child {
+                Node::Dir(d) => d.to_json(),
+                Node::File(f) => format!("{{\"name\":{},\"type\":\"file\",\"size\":{}}}", json_string(name), f.size),
+            })
+            .collect();
+        return format!(
+            "{{\"name\":{},\"type\":\"dir\",\"size\":{},\"children\":[{}]}}",
+            json_string(&self.name), self.size, children.join(",")
+        );
+    }
+}
+
+impl Directory {
+    pub fn from_disk(root: &Path) -> io::Result<Directory> {
+        fn read_into(dir: &mut Directory, on_disk: &Path) -> io::Result<()> {
+            for entry in fs::read_dir(on_disk)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    dir.add_dir(&name).unwrap();
+                    read_into(dir.dirs.get_mut(&name).unwrap(), &entry.path())?;
+                } else if file_type.is_file() {
+                    let size = i64::try_from(entry.metadata()?.len())
+                        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("{} is too large", name)))?;
+                    dir.files.push(File { name, size });
+                }
+            }
+            return Ok(());
+        }
+
+        let mut dir = Directory::empty();
+        read_into(&mut dir, root)?;
+        dir.update_sizes();
+        return Ok(dir);
+    }
+
+    pub fn transcript(&self) -> String {
+        fn list(dir: &Directory, out: &mut String) {
+            writeln!(out, "$ ls").unwrap();
+            for (name, child) in dir.children() {
+                match child {
+                    Node::Dir(_) => writeln!(out, "dir {}", name).unwrap(),
+                    Node::File(f) => writeln!(out, "{} {}", f.size, name).unwrap(),
+                }
+            }
+            for d in dir.dirs.values() {
+                writeln!(out, "$ cd {}", d.name).unwrap();
+                list(d, out);
+                writeln!(out, "$ cd ..").unwrap();
+            }
+        }
+        let mut out = String::from("$ cd /\n");
+        list(self, &mut out);
+        return out;
+    }
 }
 
 pub fn parse(input: &str) -> Directory {
-    let mut commands = input.lines();
-    // ignore the first cd / line
-    commands.next();
-    return Directory::from_commands(&mut commands);
+    return Directory::from_commands(&mut input.lines());
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let [_, mode, path] = &args[..] {
+        if mode == "disk" {
+            let dir = Directory::from_disk(Path::new(path)).expect("Failed to read directory");
+            print!("{}", dir.transcript());
+            return;
+        }
+    }
+
     let mut input = String::new();
 
     io::stdin()
@@ -102,7 +527,17 @@ fn main() {
         .expect("Failed to read input");
 
     let fs = parse(&input);
-    println!("{}", fs.dir_with_enough_space());
+    match args.get(1).map(|a| a.as_str()) {
+        Some("tree") => print!("{}", fs.tree()),
+        Some("du") => print!("{}", fs.du()),
+        Some("json") => println!("{}", fs.to_json()),
+        _ => {
+            for d in fs.small_dirs() {
+                println!("{} {}", d.path(), d.total_size());
+            }
+            println!("{}", fs.dir_with_enough_space());
+        }
+    }
 }
 
 
@@ -145,4 +580,206 @@ mod tests {
         assert_eq!(fs.dir_with_enough_space(), 24933642);
     }
 
+    #[test]
+    fn test_paths() {
+        let fs = parse(INPUT);
+        let e = fs.get("/a/e").unwrap();
+        assert_eq!((e.name(), e.path(), e.total_size()), ("e", "/a/e", 584));
+        assert_eq!(fs.parent(e).unwrap().path(), "/a");
+        assert_eq!(fs.parent(fs.get("/a").unwrap()).unwrap().path(), "/");
+        assert!(fs.parent(&fs).is_none());
+        assert!(fs.get("/a/x").is_none());
+        assert_eq!(fs.get("/d").unwrap().files()[1].name(), "d.log");
+
+        let small: Vec<&str> = fs.small_dirs().iter().map(|d| d.path()).collect();
+        assert_eq!(small, vec!["/a", "/a/e"]);
+        let entries = fs.entries();
+        assert_eq!(entries.len(), 14);
+        assert_eq!(entries[0], ("/".to_owned(), 48381165));
+        assert!(entries.contains(&("/a/e/i".to_owned(), 584)));
+        assert!(entries.contains(&("/d".to_owned(), 24933642)));
+    }
+
+    #[test]
+    fn test_transcript() {
+        let (fs, problems) = Directory::from_transcript(indoc! {"
+            $ cd /
+            $ ls
+            dir Data-2
+            100 a.txt
+            $ cd Data-2
+            $ cd new_dir
+            $ ls
+            50 x
+            $ cd /
+            $ ls
+            dir Data-2
+            100 a.txt
+            $ cd Data-2
+            $ ls
+            dir new_dir
+            7 y
+            $ cd /
+            $ ls
+            120 a.txt
+            $ cd ..
+            $ dance
+        "});
+        assert_eq!(fs.total_size(), 157);
+        assert_eq!(fs.get("/Data-2/new_dir").unwrap().total_size(), 50);
+        assert_eq!(problems, vec![
+            Inconsistency::SizeMismatch { line: 19, path: "/a.txt".to_owned(), listed: 100, now: 120 },
+            Inconsistency::MissingFromListing { line: 18, path: "/Data-2".to_owned() },
+            Inconsistency::AboveRoot { line: 20 },
+            Inconsistency::UnknownLine { line: 21, text: "$ dance".to_owned() },
+        ]);
+
+        let (fs, problems) = Directory::from_transcript(indoc! {"
+            $ cd /a/b
+            $ cd .
+            $ cd ../c/./d/
+            $ cd /y/../../x
+            $ ls
+            1 f
+            $ cd f/g
+        "});
+        let paths: Vec<&str> = fs.dirs().map(|d| d.path()).collect();
+        assert_eq!(paths, vec!["/a", "/a/b", "/a/c", "/a/c/d"]);
+        assert_eq!(fs.get("/a/c/d").unwrap().total_size(), 1);
+        assert_eq!(problems, vec![
+            Inconsistency::AboveRoot { line: 4 },
+            Inconsistency::KindMismatch { line: 7, path: "/a/c/d/f".to_owned() },
+        ]);
+
+        let (fs, problems) = Directory::from_transcript("$ cd /\n$ ls\n3000000000 big\n99999999999999999999 huge\n");
+        assert_eq!(fs.total_size(), 3000000000);
+        assert_eq!(problems, vec![Inconsistency::InvalidSize { line: 4, path: "/huge".to_owned() }]);
+
+        let (_, problems) = Directory::from_transcript("$ cd /\n$ ls\n10 x\n$ ls\n");
+        assert_eq!(problems, vec![Inconsistency::MissingFromListing { line: 4, path: "/x".to_owned() }]);
+    }
+
+    #[test]
+    fn test_dirs_order() {
+        let fs = parse(INPUT);
+        let paths: Vec<&str> = fs.dirs().map(|d| d.path()).collect();
+        assert_eq!(paths, vec!["/a", "/a/e", "/d"]);
+        assert_eq!(fs.get("/a").unwrap().total_size(), 94853);
+    }
+
+    #[test]
+    fn test_deletion_planner() {
+        let fs = parse(INPUT);
+        assert_eq!(fs.space_to_free(70000000, 30000000), 8381165);
+        assert_eq!(fs.dir_to_delete(70000000, 30000000).unwrap().path(), "/d");
+        assert_eq!(fs.dir_to_delete(50000000, 1619335).unwrap().path(), "/a/e");
+        assert!(fs.dir_to_delete(100000000, 30000000).is_none());
+
+        let paths = |dirs: Option<Vec<&Directory>>| dirs.map(|ds| ds.iter().map(|d| d.path().to_owned()).collect::<Vec<_>>());
+        assert_eq!(paths(fs.dirs_to_delete(70000000, 30000000)), Some(vec!["/d".to_owned()]));
+        assert_eq!(paths(fs.dirs_to_delete(48381165, 24933642 + 50)), Some(vec!["/d".to_owned(), "/a/e".to_owned()]));
+        assert_eq!(paths(fs.dirs_to_delete(48381165, 48381165)), None);
+        assert_eq!(paths(fs.dirs_to_delete(100000000, 1)), Some(vec![]));
+    }
+
+    #[test]
+    fn test_deletion_planner_wide() {
+        let flat = |sizes: &[i64]| {
+            let mut transcript = String::from("$ cd /\n$ ls\n");
+            for i in 0..sizes.len() {
+                writeln!(transcript, "dir d{}", i).unwrap();
+            }
+            for (i, size) in sizes.iter().enumerate() {
+                writeln!(transcript, "$ cd d{}\n$ ls\n{} f\n$ cd ..", i, size).unwrap();
+            }
+            return parse(&transcript);
+        };
+
+        let fs = flat(&[10; 40]);
+        let dirs = fs.dirs_to_delete(400, 125).unwrap();
+        assert_eq!(dirs.len(), 13);
+
+        let sizes: Vec<i64> = (0..40).map(|i| 100 + i * 7 % 41).collect();
+        let fs = flat(&sizes);
+        let dirs = fs.dirs_to_delete(fs.total_size(), 2001).unwrap();
+        assert_eq!(dirs.len(), 16);
+        assert_eq!(dirs.iter().map(|d| d.total_size()).sum::<i64>(), 2001);
+
+        // even sizes can't hit an odd target, so only the step limit ends this search
+        let sizes: Vec<i64> = (0..40).map(|i| 200 + 2 * (i * 7 % 41)).collect();
+        let fs = flat(&sizes);
+        let dirs = fs.dirs_to_delete(fs.total_size(), 4001).unwrap();
+        assert_eq!(dirs.len(), 16);
+        assert_eq!(dirs.iter().map(|d| d.total_size()).sum::<i64>(), 4002);
+    }
+
+    #[test]
+    fn test_reports() {
+        let fs = parse(INPUT);
+        assert_eq!(fs.tree(), indoc! {"
+            - / (dir, size=48381165)
+              - a (dir, size=94853)
+                - e (dir, size=584)
+                  - i (file, size=584)
+                - f (file, size=29116)
+                - g (file, size=2557)
+                - h.lst (file, size=62596)
+              - b.txt (file, size=14848514)
+              - c.dat (file, size=8504156)
+              - d (dir, size=24933642)
+                - d.ext (file, size=5626152)
+                - d.log (file, size=8033020)
+                - j (file, size=4060174)
+                - k (file, size=7214296)
+        "});
+        assert_eq!(fs.du(), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(fs.get("/a/e").unwrap().to_json(),
+            r#"{"name":"e","type":"dir","size":584,"children":[{"name":"i","type":"file","size":584}]}"#);
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+
+    #[test]
+    fn test_from_disk() {
+        let root = env::temp_dir().join(format!("day7-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a/e")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        for (path, size) in [("a/e/i", 584), ("a/f", 29116), ("b.txt", 100), ("a/h.lst", 7)] {
+            fs::File::create(root.join(path)).unwrap().set_len(size).unwrap();
+        }
+
+        let on_disk = Directory::from_disk(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let on_disk = on_disk.unwrap();
+        assert_eq!(on_disk.total_size(), 584 + 29116 + 100 + 7);
+        assert_eq!(on_disk.get("/a").unwrap().total_size(), 584 + 29116 + 7);
+        assert_eq!(on_disk.get("/empty").unwrap().total_size(), 0);
+        assert_eq!(on_disk.sum_of_small_dirs(), 29707 + 584);
+
+        let transcript = on_disk.transcript();
+        assert!(transcript.starts_with("$ cd /\n$ ls\ndir a\n100 b.txt\ndir empty\n$ cd a\n"));
+        let (round_trip, problems) = Directory::from_transcript(&transcript);
+        assert!(problems.is_empty());
+        assert_eq!(round_trip.tree(), on_disk.tree());
+
+        assert_eq!(parse(&parse(INPUT).transcript()).tree(), parse(INPUT).tree());
+    }
+
+    #[test]
+    fn test_large_sizes() {
+        let fs = parse("$ cd /\n$ ls\ndir a\n2000000000 x\n$ cd a\n$ ls\n2000000000 y\n");
+        assert_eq!(fs.total_size(), 4000000000);
+        assert_eq!(human_size(fs.total_size()), "3.8G");
+        assert_eq!(fs.dir_to_delete(5000000000, 2000000000).unwrap().path(), "/a");
+    }
+
+    #[test]
+    fn test_enough_space_already() {
+        let fs = parse("$ cd /\n$ ls\ndir a\n10 x\n$ cd a\n$ ls\n5 y\n");
+        assert_eq!(fs.dir_with_enough_space(), 5);
+        assert!(fs.dir_to_delete(70000000, 30000000).is_none());
+        assert_eq!(parse("$ cd /\n$ ls\n10 x\n").dir_with_enough_space(), 0);
+    }
+
 }
\ No newline at end of file